@@ -1,18 +1,22 @@
 //! # Analog to Digital converter
 
 use core::{
+    fmt,
     ops::DerefMut,
     pin::Pin,
     sync::atomic::{compiler_fence, Ordering},
 };
 
 use as_slice::AsMutSlice;
+use void::Void;
 
 use crate::{
+    calibration::{VrefintCal, VtempCal130, VtempCal30},
     gpio::*,
     hal::adc::{Channel, OneShot},
-    pac::ADC,
-    rcc::Rcc,
+    pac::{ADC, RCC},
+    rcc::{self, Rcc, HSI_FREQ},
+    time::Hertz,
 };
 
 use crate::dma::{self, Buffer as _};
@@ -84,12 +88,178 @@ pub enum SampleTime {
     T_160_5 = 0b111,
 }
 
+impl SampleTime {
+    /// All sampling times, from shortest to longest
+    const ALL: [SampleTime; 8] = [
+        SampleTime::T_1_5,
+        SampleTime::T_3_5,
+        SampleTime::T_7_5,
+        SampleTime::T_12_5,
+        SampleTime::T_19_5,
+        SampleTime::T_39_5,
+        SampleTime::T_79_5,
+        SampleTime::T_160_5,
+    ];
+
+    /// Returns the sampling time in ADC clock half cycles
+    fn half_cycles(self) -> u32 {
+        match self {
+            SampleTime::T_1_5 => 3,
+            SampleTime::T_3_5 => 7,
+            SampleTime::T_7_5 => 15,
+            SampleTime::T_12_5 => 25,
+            SampleTime::T_19_5 => 39,
+            SampleTime::T_39_5 => 79,
+            SampleTime::T_79_5 => 159,
+            SampleTime::T_160_5 => 321,
+        }
+    }
+}
+
+/// An analog input driven by a high-impedance source
+///
+/// Wraps a pin together with the output impedance of the source connected to
+/// it, for example a voltage divider with megaohm resistors. Such sources
+/// charge the sampling capacitor slowly, so the default sampling time gives
+/// readings that are too low. Converting it with
+/// [`Adc::read_high_impedance`] ensures the sampling time is long enough.
+///
+/// For a voltage divider, the impedance is that of both resistors in
+/// parallel.
+pub struct HighImpedance<PIN> {
+    pin: PIN,
+    impedance_ohms: u32,
+}
+
+impl<PIN> HighImpedance<PIN> {
+    /// Wraps `pin`, which is driven by a source with an output impedance of
+    /// `impedance_ohms`
+    ///
+    /// `impedance_ohms` is the source's output impedance, in ohms (R_AIN in
+    /// the datasheet). It determines the minimum sampling time, so rounding
+    /// it up is safe, but underestimating it gives readings that are too low.
+    pub fn new(pin: PIN, impedance_ohms: u32) -> Self {
+        HighImpedance {
+            pin,
+            impedance_ohms,
+        }
+    }
+
+    /// Returns the impedance of the source, in ohms
+    pub fn impedance_ohms(&self) -> u32 {
+        self.impedance_ohms
+    }
+
+    /// Releases the pin
+    pub fn release(self) -> PIN {
+        self.pin
+    }
+}
+
+/// An analog input carrying a bipolar signal, biased to an offset
+///
+/// Front-ends for signals that can be negative, like current sensors or
+/// audio, often shift the signal to mid-supply before it's converted. Wraps a
+/// pin together with the result that corresponds to a zero input (the zero
+/// code), so [`Adc::read_bipolar`] can return a signed value, centered on
+/// zero.
+///
+/// The zero code defaults to mid-scale (2048 for 12-bit results), which is
+/// right for a bias generated by dividing V_DDA in half. As such a bias is
+/// ratiometric, its code doesn't change with the supply voltage. To calibrate
+/// the zero code, connect a zero input to the front-end and call
+/// [`Adc::calibrate_bipolar_zero`], or set a known value with
+/// [`BipolarChannel::set_zero`].
+///
+/// The zero code uses the same precision and alignment as the conversion
+/// results, see [`Adc::set_precision`] and [`Adc::set_align`].
+pub struct BipolarChannel<PIN> {
+    pin: PIN,
+    zero: u16,
+}
+
+impl<PIN> BipolarChannel<PIN> {
+    /// Wraps `pin`, with a zero code of 2048 (mid-scale at 12 bits)
+    pub fn new(pin: PIN) -> Self {
+        Self::with_zero(pin, 2048)
+    }
+
+    /// Wraps `pin`, with the given zero code
+    pub fn with_zero(pin: PIN, zero: u16) -> Self {
+        BipolarChannel { pin, zero }
+    }
+
+    /// Returns the zero code
+    pub fn zero(&self) -> u16 {
+        self.zero
+    }
+
+    /// Sets the zero code
+    pub fn set_zero(&mut self, zero: u16) {
+        self.zero = zero;
+    }
+
+    /// Releases the pin
+    pub fn release(self) -> PIN {
+        self.pin
+    }
+}
+
+/// Returned, if no sampling time is long enough for a source's impedance
+#[derive(Debug)]
+pub struct ImpedanceTooHigh;
+
+/// ADC interrupt events
+#[derive(Copy, Clone, PartialEq)]
+pub enum Event {
+    /// A conversion has completed (`EOC`)
+    EndOfConversion,
+}
+
+/// ADC Clock mode
+#[derive(Copy, Clone, PartialEq)]
+pub enum ClockMode {
+    /// Dedicated asynchronous clock (HSI16)
+    ///
+    /// The ADC clock is independent of the APB clock, so the APB can be slowed
+    /// down without affecting conversions.
+    Async = 0b00,
+    /// APB clock divided by 2
+    PclkDiv2 = 0b01,
+    /// APB clock divided by 4
+    PclkDiv4 = 0b10,
+    /// APB clock
+    Pclk = 0b11,
+}
+
+/// Minimum ADC clock frequency, with the low frequency mode enabled
+const MIN_ADC_CLK: u32 = 140_000;
+
+/// Maximum startup time of the ADC voltage regulator, in microseconds
+const ADCVREG_STUP_US: u32 = 20;
+
 /// Analog to Digital converter interface
+///
+/// # Low-power run mode
+///
+/// The ADC can be used while the core is in low-power run mode. As the
+/// system clock is limited to 131.072 kHz in that mode, which is below the
+/// minimum ADC clock of 140 kHz, the ADC has to be clocked asynchronously
+/// from HSI16 ([`ClockMode::Async`], the default). HSI16 is turned on for
+/// as long as the ADC is enabled, if it isn't running already. It's turned
+/// off afterwards, unless it is used as the system clock, PLL input, MCO
+/// source or kernel clock of another peripheral by then. All waits for the
+/// ADC (enabling, conversions, disabling) poll the respective status flags,
+/// so they take as long as required at any core clock.
 pub struct Adc<State> {
     rb: ADC,
     sample_time: SampleTime,
     align: Align,
     precision: Precision,
+    clock_mode: ClockMode,
+    pclk: Hertz,
+    /// HSI16 has been turned on by the ADC, and must be turned off again
+    hsi16_enabled: bool,
     _state: State,
 }
 
@@ -97,15 +267,26 @@ impl Adc<Ready> {
     pub fn new(adc: ADC, rcc: &mut Rcc) -> Self {
         // Enable ADC clocks
         rcc.rb.apb2enr.modify(|_, w| w.adcen().set_bit());
+
+        // Enable the voltage regulator and wait for its startup time
+        // (t_ADCVREG_STUP, 20 µs at most). `asm::delay` waits at least the
+        // given number of cycles, so this never under-waits.
         adc.cr.modify(|_, w| w.advregen().set_bit());
+        cortex_m::asm::delay(rcc.clocks.sys_clk().0 / (1_000_000 / ADCVREG_STUP_US) + 1);
 
-        Self {
+        let mut adc = Self {
             rb: adc,
             sample_time: SampleTime::T_1_5,
             align: Align::Right,
             precision: Precision::B_12,
+            clock_mode: ClockMode::Async,
+            pclk: rcc.clocks.apb2_clk(),
+            hsi16_enabled: false,
             _state: Ready,
-        }
+        };
+        adc.set_clock_mode(ClockMode::Async);
+
+        adc
     }
 
     /// Set the Adc sampling time
@@ -123,6 +304,250 @@ impl Adc<Ready> {
         self.precision = precision;
     }
 
+    /// Set the Adc clock mode
+    ///
+    /// With [`ClockMode::Async`], the ADC is clocked by HSI16, independently
+    /// of the APB clock. HSI16 is turned on while the ADC is enabled, if it
+    /// isn't running already. The synchronous modes derive the ADC clock from
+    /// the APB clock.
+    ///
+    /// The low frequency mode (`CCR.LFMEN`) is enabled automatically if the
+    /// resulting ADC clock is below 3.5 MHz.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the resulting ADC clock is below the minimum of 140 kHz.
+    /// This is always the case for the synchronous modes in low-power run
+    /// mode.
+    pub fn set_clock_mode(&mut self, mode: ClockMode) {
+        let clk = match mode {
+            ClockMode::Async => HSI_FREQ,
+            ClockMode::PclkDiv2 => self.pclk.0 / 2,
+            ClockMode::PclkDiv4 => self.pclk.0 / 4,
+            ClockMode::Pclk => self.pclk.0,
+        };
+        assert!(clk >= MIN_ADC_CLK);
+
+        self.clock_mode = mode;
+        self.rb.cfgr2.modify(|_, w| w.ckmode().bits(mode as u8));
+
+        let lfmen = self.clock_frequency().0 < 3_500_000;
+        self.rb.ccr.modify(|_, w| w.lfmen().bit(lfmen));
+    }
+
+    /// Returns the frequency of the ADC clock
+    pub fn clock_frequency(&self) -> Hertz {
+        match self.clock_mode {
+            ClockMode::Async => Hertz(HSI_FREQ),
+            ClockMode::PclkDiv2 => Hertz(self.pclk.0 / 2),
+            ClockMode::PclkDiv4 => Hertz(self.pclk.0 / 4),
+            ClockMode::Pclk => self.pclk,
+        }
+    }
+
+    /// Returns the time a single conversion takes, in nanoseconds
+    ///
+    /// This includes the sampling time and the successive approximation time
+    /// for the configured precision, based on the selected clock mode.
+    pub fn conversion_time_ns(&self) -> u32 {
+        let sample_half_cycles = self.sample_time.half_cycles();
+        // t_SAR is 12.5, 11.5, 9.5 and 7.5 cycles (RM0377, section 13.5.3)
+        let conversion_half_cycles: u32 = match self.precision {
+            Precision::B_12 => 25,
+            Precision::B_10 => 23,
+            Precision::B_8 => 19,
+            Precision::B_6 => 15,
+        };
+
+        let half_cycles = u64::from(sample_half_cycles + conversion_half_cycles);
+        (half_cycles * 500_000_000 / u64::from(self.clock_frequency().0)) as u32
+    }
+
+    /// Returns the shortest sampling time for a source with the given
+    /// output impedance
+    ///
+    /// The sampling capacitor must be charged to within half an LSB of the
+    /// input voltage. Following the datasheet, this takes
+    /// `(R_AIN + R_ADC) * C_ADC * ln(2^(N + 2))`, with `R_ADC = 1 kΩ`,
+    /// `C_ADC = 16 pF` and `N` the number of bits of the configured precision.
+    ///
+    /// Returns `None`, if even the longest sampling time is too short at the
+    /// current ADC clock. Use a slower clock mode or buffer the signal then.
+    pub fn min_sample_time(&self, impedance_ohms: u32) -> Option<SampleTime> {
+        const R_ADC: u64 = 1_000;
+        const C_ADC_PF: u64 = 16;
+
+        let bits: u64 = match self.precision {
+            Precision::B_12 => 12,
+            Precision::B_10 => 10,
+            Precision::B_8 => 8,
+            Precision::B_6 => 6,
+        };
+
+        // ln(2) ≈ 693 / 1000
+        let time_ps = (u64::from(impedance_ohms) + R_ADC) * C_ADC_PF * (bits + 2) * 693 / 1000;
+        let half_cycles = time_ps * 2 * u64::from(self.clock_frequency().0) / 1_000_000_000_000;
+
+        SampleTime::ALL
+            .iter()
+            .copied()
+            .find(|t| u64::from(t.half_cycles()) > half_cycles)
+    }
+
+    /// Converts a high-impedance source, with a long enough sampling time
+    ///
+    /// If the configured sampling time is too short for the impedance of
+    /// `pin` (see [`min_sample_time`]), it is extended for this conversion
+    /// only. Otherwise, the configured sampling time is used.
+    ///
+    /// Returns [`ImpedanceTooHigh`], without starting a conversion, if no
+    /// sampling time is long enough.
+    ///
+    /// [`min_sample_time`]: #method.min_sample_time
+    pub fn read_high_impedance<PIN>(
+        &mut self,
+        pin: &mut HighImpedance<PIN>,
+    ) -> Result<u16, ImpedanceTooHigh>
+    where
+        PIN: Channel<Adc<Ready>, ID = u8>,
+    {
+        let required = self
+            .min_sample_time(pin.impedance_ohms)
+            .ok_or(ImpedanceTooHigh)?;
+
+        let configured = self.sample_time;
+        if (required as u8) > (configured as u8) {
+            self.sample_time = required;
+        }
+
+        // Can't fail, as the `OneShot` implementation blocks and never
+        // returns an error.
+        let result: nb::Result<u16, ()> = OneShot::read(self, &mut pin.pin);
+        self.sample_time = configured;
+
+        Ok(result.unwrap_or(0))
+    }
+
+    /// Converts a bipolar signal, returning the result relative to its zero
+    /// code
+    ///
+    /// See [`BipolarChannel`].
+    pub fn read_bipolar<PIN>(&mut self, channel: &mut BipolarChannel<PIN>) -> i16
+    where
+        PIN: Channel<Adc<Ready>, ID = u8>,
+    {
+        // Can't fail, as the `OneShot` implementation blocks and never
+        // returns an error.
+        let result: nb::Result<u16, ()> = OneShot::read(self, &mut channel.pin);
+        let value = i32::from(result.unwrap_or(0)) - i32::from(channel.zero);
+
+        // Only left-aligned results can exceed the range of `i16`
+        value.max(i32::from(i16::MIN)).min(i32::from(i16::MAX)) as i16
+    }
+
+    /// Converts a bipolar signal, returning the voltage relative to its zero
+    /// code in millivolts
+    ///
+    /// V_DDA is measured in the same scan, see [`read_internal_with`], so the
+    /// result is correct, even if the supply voltage drifts. The zero code is
+    /// interpreted as a 12-bit, right-aligned value here, regardless of the
+    /// configured precision and alignment.
+    ///
+    /// [`read_internal_with`]: #method.read_internal_with
+    pub fn read_bipolar_millivolts<PIN>(&mut self, channel: &mut BipolarChannel<PIN>) -> i32
+    where
+        PIN: Channel<Adc<Ready>, ID = u8>,
+    {
+        let (vdda, _, voltage) = self.read_internal_with(&mut channel.pin);
+        let zero = u32::from(channel.zero) * u32::from(vdda.0) / 4095;
+
+        i32::from(voltage.0) - zero as i32
+    }
+
+    /// Sets the zero code of `channel` to the average of `samples`
+    /// conversions
+    ///
+    /// A zero signal must be applied to the front-end while this runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `samples` is 0.
+    pub fn calibrate_bipolar_zero<PIN>(&mut self, channel: &mut BipolarChannel<PIN>, samples: u16)
+    where
+        PIN: Channel<Adc<Ready>, ID = u8>,
+    {
+        assert!(samples > 0);
+
+        let mut sum = 0;
+        for _ in 0..samples {
+            let result: nb::Result<u16, ()> = OneShot::read(self, &mut channel.pin);
+            sum += u32::from(result.unwrap_or(0));
+        }
+
+        let samples = u32::from(samples);
+        channel.zero = ((sum + samples / 2) / samples) as u16;
+    }
+
+    /// Starts listening for an interrupt event
+    pub fn listen(&mut self, event: Event) {
+        match event {
+            Event::EndOfConversion => self.rb.ier.modify(|_, w| w.eocie().set_bit()),
+        }
+    }
+
+    /// Stops listening for an interrupt event
+    pub fn unlisten(&mut self, event: Event) {
+        match event {
+            Event::EndOfConversion => self.rb.ier.modify(|_, w| w.eocie().clear_bit()),
+        }
+    }
+
+    /// Starts a single conversion of `channel`, without waiting for it
+    ///
+    /// Use [`Adc::read_result`] to get the result, for example from the ADC
+    /// interrupt handler after calling `listen(Event::EndOfConversion)`. This
+    /// allows the core to sleep (e.g. using WFI) during the conversion.
+    pub fn start_conversion<PIN>(&mut self, _: &mut PIN)
+    where
+        PIN: Channel<Adc<Ready>, ID = u8>,
+    {
+        // Clear a stale end of conversion flag
+        self.rb.isr.modify(|_, w| w.eoc().set_bit());
+
+        self.power_up();
+        self.configure(
+            Channels {
+                flags: 0x1 << PIN::channel(),
+            },
+            false,
+            None,
+            false,
+        );
+    }
+
+    /// Returns the result of a conversion started by `start_conversion`
+    ///
+    /// Returns `WouldBlock`, if the conversion hasn't completed yet. Reading
+    /// the result clears the `EOC` flag, the end of sequence flag is cleared
+    /// by writing to `ISR`. The ADC is powered down afterwards.
+    pub fn read_result(&mut self) -> nb::Result<u16, Void> {
+        if self.rb.isr.read().eoc().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let res = self.rb.dr.read().bits() as u16;
+        let val = if self.align == Align::Left && self.precision == Precision::B_6 {
+            res << 8
+        } else {
+            res
+        };
+
+        self.rb.isr.modify(|_, w| w.eos().set_bit());
+        self.power_down();
+
+        Ok(val)
+    }
+
     /// Starts a continuous conversion process
     ///
     /// The `channel` argument specifies which channel should be converted.
@@ -200,13 +625,16 @@ impl Adc<Ready> {
         let continous = trigger.is_none();
 
         self.power_up();
-        self.configure(channels, continous, trigger);
+        self.configure(channels, continous, trigger, false);
 
         Adc {
             rb: self.rb,
             sample_time: self.sample_time,
             align: self.align,
             precision: self.precision,
+            clock_mode: self.clock_mode,
+            pclk: self.pclk,
+            hsi16_enabled: self.hsi16_enabled,
             _state: Active {
                 buffer: buffer_unsafe,
                 transfer,
@@ -215,6 +643,364 @@ impl Adc<Ready> {
     }
 }
 
+impl Adc<Ready> {
+    /// Starts a low-power scan that fills `buffer` exactly once
+    ///
+    /// Like [`Adc::start`], but intended for slow periodic sampling, where
+    /// the ADC should consume as little power as possible and must never
+    /// overrun. The ADC is configured with:
+    /// - Auto-delay (`WAIT`): A conversion only starts once the result of the
+    ///   previous conversion has been read from the data register by the DMA.
+    /// - Auto-off (`AUTOFF`): The ADC is powered down between conversions and
+    ///   powered up again automatically on the next trigger.
+    /// - DMA one-shot mode: The DMA writes each result into `buffer`, until
+    ///   the buffer is full. It doesn't wrap around.
+    ///
+    /// Conversions occur as follows:
+    /// - With a `trigger`, every trigger event converts each channel of
+    ///   `channels` once, in ascending channel order. Trigger events that
+    ///   occur while a sequence is still being converted are ignored.
+    /// - Without a trigger, the channels are converted continuously, as fast
+    ///   as the DMA reads the results.
+    ///
+    /// Once `buffer` is full, the DMA stops reading the data register. The
+    /// ADC converts at most one more value into the data register, which is
+    /// never read, and then waits, without overrunning, until the scan is
+    /// stopped by [`Adc::wait_scan`]. That last value is discarded.
+    ///
+    /// As the auto-delay depends on the DMA reading each result, the DMA
+    /// channel should not be shared with transfers that could delay it by
+    /// more than one trigger period, or trigger events are lost.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `buffer` is larger than 65535.
+    pub fn start_low_power_scan<DmaChan, Buf>(
+        mut self,
+        channels: impl Into<Channels>,
+        trigger: Option<Trigger>,
+        dma: &mut dma::Handle,
+        dma_chan: DmaChan,
+        buffer: Pin<Buf>,
+    ) -> Adc<Scan<DmaChan, Buf>>
+    where
+        DmaToken: dma::Target<DmaChan>,
+        Buf: DerefMut + 'static,
+        Buf::Target: AsMutSlice<Element = u16>,
+        DmaChan: dma::Channel,
+    {
+        // See `Adc::start` for why this is sound.
+        let dma_token = DmaToken(());
+
+        let num_words = (*buffer).len();
+
+        // Safe, because we're only taking the address of a register.
+        let address = &self.rb.dr as *const _ as u32;
+
+        // Safe, because the trait bounds of this method guarantee that the
+        // buffer can be written to.
+        let transfer = unsafe {
+            dma::Transfer::new(
+                dma,
+                dma_token,
+                dma_chan,
+                buffer,
+                num_words,
+                address,
+                dma::Priority::high(),
+                dma::Direction::peripheral_to_memory(),
+                false,
+            )
+        }
+        .start();
+
+        let continous = trigger.is_none();
+
+        self.power_up();
+        self.configure(channels, continous, trigger, true);
+
+        Adc {
+            rb: self.rb,
+            sample_time: self.sample_time,
+            align: self.align,
+            precision: self.precision,
+            clock_mode: self.clock_mode,
+            pclk: self.pclk,
+            hsi16_enabled: self.hsi16_enabled,
+            _state: Scan { transfer },
+        }
+    }
+
+    /// Converts all channels of `sequence` once, blocking
+    ///
+    /// The results are written to `buffer` in the order the channels were
+    /// added to the sequence, which is ascending channel order (see
+    /// [`AdcSequence`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `sequence` is empty, or if the length of `buffer` doesn't
+    /// match the number of channels in `sequence`.
+    pub fn read_sequence(&mut self, sequence: &AdcSequence, buffer: &mut [u16]) {
+        assert!(!sequence.is_empty());
+        assert_eq!(buffer.len(), sequence.len());
+
+        self.power_up();
+        // Use auto-delay, so the next conversion only starts once we've read
+        // the previous result and can't overrun.
+        self.configure(sequence, false, None, true);
+
+        for value in buffer.iter_mut() {
+            while self.rb.isr.read().eoc().bit_is_clear() {}
+
+            let res = self.rb.dr.read().bits() as u16;
+            *value = if self.align == Align::Left && self.precision == Precision::B_6 {
+                res << 8
+            } else {
+                res
+            };
+        }
+
+        while self.rb.isr.read().eos().bit_is_clear() {}
+        self.rb.isr.modify(|_, w| w.eos().set_bit());
+        self.power_down();
+    }
+
+    /// Measures V_DDA and the die temperature in a single scan
+    ///
+    /// The internal voltage reference and the temperature sensor are converted
+    /// back to back, and the factory calibration data (see
+    /// [`crate::calibration`]) is applied to both. The temperature is
+    /// compensated for the V_DDA measured in the same scan, so the result
+    /// isn't affected by supply drift between two separate conversions.
+    ///
+    /// Both internal channels are enabled for the duration of the scan, if
+    /// they aren't already. The configured precision, alignment and sampling
+    /// time are overridden as required by the internal channels, and restored
+    /// afterwards.
+    pub fn read_internal(&mut self) -> (Millivolts, Celsius) {
+        let sequence = AdcSequence::builder().add(&VRef).add(&VTemp).build();
+
+        let mut buffer = [0; 2];
+        self.read_internal_sequence(&sequence, &mut buffer);
+
+        internal_measurements(buffer[0], buffer[1])
+    }
+
+    /// Measures V_DDA, the die temperature and an external channel in a
+    /// single scan
+    ///
+    /// Like [`read_internal`], but also converts `pin`. Its voltage is
+    /// returned as the third value, calculated from the V_DDA measured in the
+    /// same scan.
+    ///
+    /// [`read_internal`]: #method.read_internal
+    pub fn read_internal_with<PIN>(&mut self, pin: &mut PIN) -> (Millivolts, Celsius, Millivolts)
+    where
+        PIN: Channel<Adc<Ready>, ID = u8>,
+    {
+        // External channels are numbered 0 to 15, so they always come before
+        // the internal ones.
+        let sequence = AdcSequence::builder()
+            .add(pin)
+            .add(&VRef)
+            .add(&VTemp)
+            .build();
+
+        let mut buffer = [0; 3];
+        self.read_internal_sequence(&sequence, &mut buffer);
+
+        let (vdda, temperature) = internal_measurements(buffer[1], buffer[2]);
+        let voltage = u32::from(buffer[0]) * u32::from(vdda.0) / 4095;
+
+        (vdda, temperature, Millivolts(voltage as u16))
+    }
+
+    fn read_internal_sequence(&mut self, sequence: &AdcSequence, buffer: &mut [u16]) {
+        let ccr = self.rb.ccr.read();
+        let (vrefen, tsen) = (ccr.vrefen().bit_is_set(), ccr.tsen().bit_is_set());
+        self.rb
+            .ccr
+            .modify(|_, w| w.vrefen().set_bit().tsen().set_bit());
+
+        let (sample_time, align, precision) = (self.sample_time, self.align, self.precision);
+        self.align = Align::Right;
+        self.precision = Precision::B_12;
+
+        // Both internal channels need to be sampled for at least 10 µs.
+        let min_half_cycles =
+            u64::from(self.clock_frequency().0) * 2 * MIN_INTERNAL_SAMPLE_NS / 1_000_000_000;
+        self.sample_time = SampleTime::ALL
+            .iter()
+            .copied()
+            .find(|t| u64::from(t.half_cycles()) >= min_half_cycles)
+            .unwrap_or(SampleTime::T_160_5);
+
+        // The channels need some time to start up, if they weren't enabled
+        // already. Discard the results of the first scan in that case.
+        if !vrefen || !tsen {
+            self.read_sequence(sequence, buffer);
+        }
+        self.read_sequence(sequence, buffer);
+
+        self.sample_time = sample_time;
+        self.align = align;
+        self.precision = precision;
+
+        self.rb
+            .ccr
+            .modify(|_, w| w.vrefen().bit(vrefen).tsen().bit(tsen));
+    }
+
+    /// Prepares the ADC for repeated, low-latency conversions of `pin`
+    ///
+    /// The ADC is enabled and fully configured for `pin` once. The returned
+    /// [`PreparedChannel`] then only has to start a conversion and wait for
+    /// its result, see [`PreparedChannel::read_fast`]. The ADC is disabled
+    /// again, once the `PreparedChannel` is dropped.
+    ///
+    /// The sampling time, alignment and precision that are configured at the
+    /// time of this call are used for all conversions.
+    pub fn prepare<PIN>(&mut self, _: &mut PIN) -> PreparedChannel<'_>
+    where
+        PIN: Channel<Adc<Ready>, ID = u8>,
+    {
+        self.power_up();
+
+        self.rb.cfgr1.write(|w| {
+            w.res().bits(self.precision as u8);
+            w.align().bit(self.align == Align::Left)
+        });
+        self.rb
+            .smpr
+            .modify(|_, w| w.smp().bits(self.sample_time as u8));
+        self.rb.chselr.write(|w|
+            // Safe, as long as there are no `Channel` implementations that
+            // define invalid values.
+            unsafe { w.bits(0x1 << PIN::channel()) });
+
+        let shift = if self.align == Align::Left && self.precision == Precision::B_6 {
+            8
+        } else {
+            0
+        };
+
+        PreparedChannel { adc: self, shift }
+    }
+}
+
+/// An ADC that is prepared for low-latency conversions of a single channel
+///
+/// Created by [`Adc::prepare`].
+pub struct PreparedChannel<'a> {
+    adc: &'a mut Adc<Ready>,
+    shift: u8,
+}
+
+impl PreparedChannel<'_> {
+    /// Converts the prepared channel, blocking
+    ///
+    /// This only starts the conversion, waits for the end of conversion and
+    /// reads the result, which also clears the end of conversion flag.
+    #[inline]
+    pub fn read_fast(&mut self) -> u16 {
+        let rb = &self.adc.rb;
+        rb.cr.modify(|_, w| w.adstart().set_bit());
+        while rb.isr.read().eoc().bit_is_clear() {}
+        (rb.dr.read().bits() as u16) << self.shift
+    }
+}
+
+impl Drop for PreparedChannel<'_> {
+    fn drop(&mut self) {
+        self.adc.power_down();
+    }
+}
+
+/// Minimum sampling time of the internal voltage reference and temperature
+/// sensor, in nanoseconds
+const MIN_INTERNAL_SAMPLE_NS: u64 = 10_000;
+
+/// Calculates V_DDA and the temperature from the raw 12-bit results of the
+/// internal voltage reference and the temperature sensor
+fn internal_measurements(vref: u16, temp: u16) -> (Millivolts, Celsius) {
+    let vref_cal = i32::from(VrefintCal::get().read());
+    let temp_cal30 = i32::from(VtempCal30::get().read());
+    let temp_cal130 = i32::from(VtempCal130::get().read());
+
+    // Guard against a division by zero, if the ADC returned garbage.
+    let vref = i32::from(vref).max(1);
+
+    // The calibration values were acquired at V_DDA = 3 V.
+    let vdda = 3000 * vref_cal / vref;
+
+    // Scale the sensor result to what it would have been at V_DDA = 3 V, then
+    // interpolate between the two calibration points.
+    let temp = i32::from(temp) * vref_cal / vref;
+    let slope = (temp_cal130 - temp_cal30).max(1);
+    let offset = (temp - temp_cal30) * (130 - 30);
+    let celsius = 30 + (offset + offset.signum() * slope / 2) / slope;
+
+    (Millivolts(vdda as u16), Celsius(celsius as i16))
+}
+
+impl<DmaChan, Buf> Adc<Scan<DmaChan, Buf>>
+where
+    DmaChan: dma::Channel,
+{
+    /// Indicates whether `buffer` has been filled
+    pub fn is_complete(&self) -> bool {
+        !self._state.transfer.is_active()
+    }
+
+    /// Waits for the scan to fill `buffer` and stops the ADC
+    ///
+    /// This function will busily wait until the buffer is full. If you don't
+    /// want this, please call it only once [`Adc::is_complete`] returns
+    /// `true`.
+    pub fn wait_scan(self) -> ScanResult<DmaChan, Buf> {
+        let result = self._state.transfer.wait();
+
+        // Stop the conversion that might be waiting for its result to be read
+        self.rb.cr.modify(|_, w| w.adstp().set_bit());
+        while self.rb.cr.read().adstart().bit_is_set() {}
+        self.rb
+            .cfgr1
+            .modify(|_, w| w.wait().clear_bit().autoff().clear_bit());
+        self.rb
+            .isr
+            .modify(|_, w| w.ovr().set_bit().eoc().set_bit().eos().set_bit());
+        self.power_down();
+
+        let adc = Adc {
+            rb: self.rb,
+            sample_time: self.sample_time,
+            align: self.align,
+            precision: self.precision,
+            clock_mode: self.clock_mode,
+            pclk: self.pclk,
+            hsi16_enabled: self.hsi16_enabled,
+            _state: Ready,
+        };
+
+        match result {
+            Ok(res) => Ok(ScanResources {
+                adc,
+                dma_chan: res.channel,
+                buffer: res.buffer,
+            }),
+            Err((res, err)) => Err((
+                ScanResources {
+                    adc,
+                    dma_chan: res.channel,
+                    buffer: res.buffer,
+                },
+                err,
+            )),
+        }
+    }
+}
+
 impl<DmaChan, Buffer> Adc<Active<DmaChan, Buffer>>
 where
     DmaChan: dma::Channel,
@@ -244,6 +1030,20 @@ impl<State> Adc<State> {
     }
 
     fn power_up(&mut self) {
+        // The ADC doesn't become ready without a clock
+        if self.clock_mode == ClockMode::Async {
+            // Safe, as we only modify the HSI16 enable bit, and do so within a
+            // critical section.
+            let rcc = unsafe { &*RCC::ptr() };
+            cortex_m::interrupt::free(|_| {
+                if rcc.cr.read().hsi16on().bit_is_clear() {
+                    rcc.cr.modify(|_, w| w.hsi16on().set_bit());
+                    self.hsi16_enabled = true;
+                }
+            });
+            while rcc.cr.read().hsi16rdyf().bit_is_clear() {}
+        }
+
         self.rb.isr.modify(|_, w| w.adrdy().set_bit());
         self.rb.cr.modify(|_, w| w.aden().set_bit());
         while self.rb.isr.read().adrdy().bit_is_clear() {}
@@ -253,17 +1053,40 @@ impl<State> Adc<State> {
         self.rb.cr.modify(|_, w| w.addis().set_bit());
         self.rb.isr.modify(|_, w| w.adrdy().set_bit());
         while self.rb.cr.read().aden().bit_is_set() {}
+
+        if self.hsi16_enabled {
+            // Safe, as we only modify the HSI16 enable bit, and do so within a
+            // critical section.
+            let rcc = unsafe { &*RCC::ptr() };
+            cortex_m::interrupt::free(|_| {
+                // Something else might have started using HSI16 meanwhile
+                if !rcc::hsi16_in_use() {
+                    rcc.cr.modify(|_, w| w.hsi16on().clear_bit());
+                }
+            });
+            self.hsi16_enabled = false;
+        }
     }
 
-    fn configure(&mut self, channels: impl Into<Channels>, cont: bool, trigger: Option<Trigger>) {
+    fn configure(
+        &mut self,
+        channels: impl Into<Channels>,
+        cont: bool,
+        trigger: Option<Trigger>,
+        low_power: bool,
+    ) {
         self.rb.cfgr1.write(|w| {
             w.res().bits(self.precision as u8);
             w.cont().bit(cont);
             w.align().bit(self.align == Align::Left);
-            // DMA circular mode
-            w.dmacfg().set_bit();
+            // DMA circular mode, or one-shot mode for low-power scans
+            w.dmacfg().bit(!low_power);
             // Generate DMA requests
             w.dmaen().set_bit();
+            // Only start the next conversion once the previous result has been
+            // read, and power the ADC down in between
+            w.wait().bit(low_power);
+            w.autoff().bit(low_power);
 
             if let Some(trigger) = trigger {
                 // Select hardware trigger
@@ -304,6 +1127,7 @@ where
             },
             false,
             None,
+            false,
         );
 
         while self.rb.isr.read().eos().bit_is_clear() {}
@@ -320,6 +1144,14 @@ where
     }
 }
 
+/// A voltage, in millivolts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Millivolts(pub u16);
+
+/// A temperature, in degrees Celsius
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Celsius(pub i16);
+
 /// Indicates that the ADC peripheral is ready
 pub struct Ready;
 
@@ -329,6 +1161,137 @@ pub struct Active<DmaChan, Buf> {
     buffer: Buffer,
 }
 
+/// Indicates that the ADC peripheral is performing a low-power scan
+///
+/// See [`Adc::start_low_power_scan`].
+pub struct Scan<DmaChan, Buf> {
+    transfer: dma::Transfer<DmaToken, DmaChan, Buf, dma::Started>,
+}
+
+/// The resources returned by [`Adc::wait_scan`]
+pub struct ScanResources<DmaChan, Buf> {
+    pub adc: Adc<Ready>,
+    pub dma_chan: DmaChan,
+    pub buffer: Pin<Buf>,
+}
+
+// See `dma::TransferResources` for why `Debug` isn't derived.
+impl<DmaChan, Buf> fmt::Debug for ScanResources<DmaChan, Buf> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ScanResources {{ ... }}")
+    }
+}
+
+pub type ScanResult<DmaChan, Buf> =
+    Result<ScanResources<DmaChan, Buf>, (ScanResources<DmaChan, Buf>, dma::Error)>;
+
+/// A reusable sequence of channels, created by [`SequenceBuilder`]
+///
+/// # Channel order
+///
+/// The ADC of the STM32L0 can't convert channels in an arbitrary order. It
+/// always scans the selected channels in ascending order of their channel
+/// number. To make sure that the results end up where they are expected,
+/// [`SequenceBuilder::add`] only accepts channels in that order, so the
+/// index of a result in the buffer is the position the channel was added
+/// at.
+///
+/// An `AdcSequence` can be passed to [`Adc::read_sequence`], or to
+/// [`Adc::start`] and [`Adc::start_low_power_scan`] (as `Channels`), as
+/// often as needed.
+#[derive(Clone, Copy, Debug)]
+pub struct AdcSequence {
+    flags: u32,
+    len: usize,
+}
+
+impl AdcSequence {
+    /// Returns a builder for a new sequence
+    pub fn builder() -> SequenceBuilder {
+        SequenceBuilder {
+            sequence: AdcSequence { flags: 0, len: 0 },
+            last: None,
+        }
+    }
+
+    /// Returns the number of channels in the sequence
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Indicates whether the sequence contains no channels
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the index of the result of `channel` in the result buffer
+    pub fn position<C>(&self, _: &C) -> Option<usize>
+    where
+        C: Channel<Adc<Ready>, ID = u8>,
+    {
+        let bit = 0x1 << C::channel();
+        if self.flags & bit == 0 {
+            return None;
+        }
+
+        Some((self.flags & (bit - 1)).count_ones() as usize)
+    }
+}
+
+impl From<&AdcSequence> for Channels {
+    fn from(sequence: &AdcSequence) -> Self {
+        Channels {
+            flags: sequence.flags,
+        }
+    }
+}
+
+impl From<AdcSequence> for Channels {
+    fn from(sequence: AdcSequence) -> Self {
+        Channels {
+            flags: sequence.flags,
+        }
+    }
+}
+
+/// Builds an [`AdcSequence`]
+pub struct SequenceBuilder {
+    sequence: AdcSequence,
+    last: Option<u8>,
+}
+
+impl SequenceBuilder {
+    /// Adds `channel` to the end of the sequence
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the channel number of `channel` isn't higher than the one
+    /// of the previously added channel. The ADC converts the channels in
+    /// ascending order, see [`AdcSequence`].
+    pub fn add<C>(mut self, _: &C) -> Self
+    where
+        C: Channel<Adc<Ready>, ID = u8>,
+    {
+        let channel = C::channel();
+        if let Some(last) = self.last {
+            assert!(
+                channel > last,
+                "ADC channels must be added in ascending order"
+            );
+        }
+
+        self.sequence.flags |= 0x1 << channel;
+        self.sequence.len += 1;
+        self.last = Some(channel);
+        self
+    }
+
+    /// Returns the finished sequence
+    pub fn build(self) -> AdcSequence {
+        self.sequence
+    }
+}
+
 /// A collection of channels
 ///
 /// Used to set up multi-channel conversions.
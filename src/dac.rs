@@ -0,0 +1,259 @@
+//! Digital-to-analog converter
+//!
+//! Channel 1 of the DAC is routed to PA4. The channel implements [`AnalogOut`],
+//! which plays the same role for analog outputs that `embedded_hal::PwmPin`
+//! plays for PWM outputs, so drivers can be written against either.
+//!
+//! [`C1::drive_with`] streams samples computed by a closure, with the timing
+//! given by a timer's trigger output.
+
+use cortex_m::interrupt;
+
+use crate::gpio::gpioa::PA4;
+use crate::gpio::Analog;
+use crate::pac::{tim2, tim21, tim6, DAC, TIM2, TIM21, TIM6};
+use crate::rcc::Rcc;
+use crate::timer::Timer;
+
+/// An analog output
+///
+/// Modelled after `embedded_hal::PwmPin`.
+pub trait AnalogOut {
+    /// Type for the `value` methods
+    type Value;
+
+    /// Disables the output
+    fn disable(&mut self);
+
+    /// Enables the output
+    fn enable(&mut self);
+
+    /// Returns the value that is currently being output
+    fn get_value(&self) -> Self::Value;
+
+    /// Returns the maximum value that can be passed to `set_value`
+    fn max_value(&self) -> Self::Value;
+
+    /// Sets a new output value
+    fn set_value(&mut self, value: Self::Value);
+}
+
+/// Extension trait that constrains the `DAC` peripheral
+pub trait DacExt {
+    /// Enables the DAC and returns its first channel, which drives `pin`
+    fn dac(self, pin: PA4<Analog>, rcc: &mut Rcc) -> C1;
+}
+
+impl DacExt for DAC {
+    fn dac(self, pin: PA4<Analog>, rcc: &mut Rcc) -> C1 {
+        C1::new(self, pin, rcc)
+    }
+}
+
+/// DAC channel 1, on PA4
+pub struct C1 {
+    dac: DAC,
+    pin: PA4<Analog>,
+}
+
+impl C1 {
+    /// Resets and enables the DAC, and returns its first channel
+    ///
+    /// The channel starts disabled, with an output value of 0. Call
+    /// [`AnalogOut::enable`] to drive `pin`.
+    pub fn new(dac: DAC, pin: PA4<Analog>, rcc: &mut Rcc) -> Self {
+        // Reset peripheral
+        rcc.reset::<DAC>();
+
+        // Enable peripheral clock
+        rcc.rb.apb1enr.modify(|_, w| w.dacen().set_bit());
+
+        C1 { dac, pin }
+    }
+
+    /// Sets the output using an 8-bit right-aligned value (`DHR8R1`)
+    pub fn set_value_8bit(&mut self, value: u8) {
+        self.dac.dhr8r1.write(|w| w.dacc1dhr().bits(value));
+    }
+
+    /// Sets the output using a 12-bit right-aligned value (`DHR12R1`)
+    ///
+    /// Values above 0xFFF are clamped to 0xFFF, the full-scale output.
+    pub fn set_value_12bit(&mut self, value: u16) {
+        let value = core::cmp::min(value, 0xfff);
+        // Safe, as the value fits into the 12 bit wide field.
+        self.dac
+            .dhr12r1
+            .write(|w| unsafe { w.dacc1dhr().bits(value) });
+    }
+
+    /// Streams samples computed by `f`, timed by `timer`
+    ///
+    /// `f` is called with a sample counter (the phase), which starts at 0 and
+    /// wraps around, and returns the next 12-bit sample. The timer's update
+    /// event is selected as the DAC trigger, so samples are output exactly at
+    /// the timer's frequency. The timer's update interrupt is enabled, and
+    /// [`Driven::on_interrupt`] must be called from its handler to compute
+    /// the sample for the next trigger.
+    ///
+    /// This needs no sample buffer and allows arbitrary waveforms, but costs
+    /// an interrupt per sample. Depending on the closure, the handler takes
+    /// roughly 50 to 150 core clock cycles, so at 32 MHz, sample rates of up
+    /// to about 100 kHz are possible, leaving little time for anything else.
+    /// Keep the sample rate well below that in practice.
+    ///
+    /// The channel is enabled by this method.
+    pub fn drive_with<T, F>(self, mut timer: T, f: F) -> Driven<T, F>
+    where
+        T: TriggerTimer,
+        F: FnMut(u32) -> u16,
+    {
+        timer.select_update_trigger();
+
+        // Safe, as `TriggerTimer` is only implemented for valid trigger
+        // selections.
+        interrupt::free(|_| {
+            self.dac
+                .cr
+                .modify(|_, w| unsafe { w.tsel1().bits(T::TSEL).ten1().set_bit().en1().set_bit() })
+        });
+
+        let mut driven = Driven {
+            channel: self,
+            timer,
+            f,
+            phase: 0,
+        };
+
+        // Load the first sample, so it is output on the first trigger
+        driven.load_next();
+        driven.timer.listen();
+
+        driven
+    }
+
+    /// Releases the DAC peripheral and the pin
+    pub fn release(self) -> (DAC, PA4<Analog>) {
+        (self.dac, self.pin)
+    }
+}
+
+/// A timer that can trigger DAC conversions
+///
+/// Implemented for timers whose trigger output is connected to the DAC.
+pub trait TriggerTimer {
+    /// Trigger selection for this timer (`CR.TSEL1`)
+    const TSEL: u8;
+
+    /// Selects the update event as the trigger output
+    fn select_update_trigger(&mut self);
+
+    /// Enables the update interrupt
+    fn listen(&mut self);
+
+    /// Disables the update interrupt
+    fn unlisten(&mut self);
+
+    /// Clears the update interrupt flag
+    fn clear_irq(&mut self);
+}
+
+macro_rules! trigger_timers {
+    ($($TIM:ident: ($tsel:expr, $mms:ty),)+) => {
+        $(
+            impl TriggerTimer for Timer<$TIM> {
+                const TSEL: u8 = $tsel;
+
+                fn select_update_trigger(&mut self) {
+                    self.select_master_mode(<$mms>::UPDATE);
+                }
+
+                fn listen(&mut self) {
+                    Timer::<$TIM>::listen(self);
+                }
+
+                fn unlisten(&mut self) {
+                    Timer::<$TIM>::unlisten(self);
+                }
+
+                fn clear_irq(&mut self) {
+                    Timer::<$TIM>::clear_irq(self);
+                }
+            }
+        )+
+    }
+}
+
+trigger_timers! {
+    TIM6: (0b000, tim6::cr2::MMS_A),
+    TIM21: (0b011, tim21::cr2::MMS_A),
+    TIM2: (0b100, tim2::cr2::MMS_A),
+}
+
+/// DAC channel 1, driven by a closure
+///
+/// Created by [`C1::drive_with`].
+pub struct Driven<T, F> {
+    channel: C1,
+    timer: T,
+    f: F,
+    phase: u32,
+}
+
+impl<T, F> Driven<T, F>
+where
+    T: TriggerTimer,
+    F: FnMut(u32) -> u16,
+{
+    /// Computes the next sample
+    ///
+    /// Call this from the timer's interrupt handler. It clears the timer's
+    /// interrupt flag.
+    pub fn on_interrupt(&mut self) {
+        self.timer.clear_irq();
+        self.load_next();
+    }
+
+    /// Stops streaming and returns the channel and the timer
+    ///
+    /// The channel is left enabled, but software triggered again, so the
+    /// last sample stays on the output until it is changed.
+    pub fn release(mut self) -> (C1, T) {
+        self.timer.unlisten();
+
+        let dac = &self.channel.dac;
+        interrupt::free(|_| dac.cr.modify(|_, w| w.ten1().clear_bit()));
+
+        (self.channel, self.timer)
+    }
+
+    fn load_next(&mut self) {
+        let sample = (self.f)(self.phase);
+        self.phase = self.phase.wrapping_add(1);
+        self.channel.set_value_12bit(sample);
+    }
+}
+
+impl AnalogOut for C1 {
+    type Value = u16;
+
+    fn disable(&mut self) {
+        interrupt::free(|_| self.dac.cr.modify(|_, w| w.en1().clear_bit()))
+    }
+
+    fn enable(&mut self) {
+        interrupt::free(|_| self.dac.cr.modify(|_, w| w.en1().set_bit()))
+    }
+
+    fn get_value(&self) -> u16 {
+        self.dac.dor1.read().dacc1dor().bits()
+    }
+
+    fn max_value(&self) -> u16 {
+        0xfff
+    }
+
+    fn set_value(&mut self, value: u16) {
+        self.set_value_12bit(value)
+    }
+}
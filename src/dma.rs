@@ -13,16 +13,17 @@
 
 use core::{
     fmt, mem,
-    ops::Deref,
+    ops::{Deref, DerefMut},
     pin::Pin,
     sync::atomic::{compiler_fence, Ordering},
 };
 
-use as_slice::AsSlice;
+use as_slice::{AsMutSlice, AsSlice};
 
 use crate::{
     adc,
-    pac::{self, dma1::ch::cr},
+    pac::{self, dma1::ch::cr, ADC, TIM2, TIM3},
+    pwm,
     rcc::Rcc,
 };
 
@@ -126,16 +127,65 @@ where
         B: Deref,
         B::Target: Buffer<Word>,
         Word: SupportedWordSize,
+    {
+        Self::new_with_peripheral_size::<Word, Word>(
+            handle, target, channel, buffer, num_words, address, priority, dir, circular,
+        )
+    }
+
+    /// Internal constructor for transfers with different word sizes
+    ///
+    /// Like [`Transfer::new`], but the peripheral word size (`PSIZE`) is
+    /// `PeripheralWord`, while the memory word size (`MSIZE`) is `Word`.
+    ///
+    /// The DMA doesn't pack or unpack data. Every one of the `num_words`
+    /// transfers reads one word from the source and writes one word to the
+    /// destination:
+    /// - If the source word is larger, it is truncated to its least
+    ///   significant bits.
+    /// - If the source word is smaller, it is zero-extended.
+    ///
+    /// For example, reading a 16-bit peripheral register into a `u8` buffer
+    /// stores the lower 8 bits of each value, and writing a `u8` buffer into a
+    /// 16-bit register writes each byte zero-extended.
+    ///
+    /// # Safety
+    ///
+    /// See [`Transfer::new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the length of the buffer is larger than `u16::max_value()`.
+    ///
+    /// Panics, if the buffer is not aligned to `Word`, or `address` is not
+    /// aligned to `PeripheralWord`.
+    pub(crate) unsafe fn new_with_peripheral_size<Word, PeripheralWord>(
+        handle: &mut Handle,
+        target: T,
+        channel: C,
+        buffer: Pin<B>,
+        num_words: usize,
+        address: u32,
+        priority: Priority,
+        dir: Direction,
+        circular: bool,
+    ) -> Self
+    where
+        B: Deref,
+        B::Target: Buffer<Word>,
+        Word: SupportedWordSize,
+        PeripheralWord: SupportedWordSize,
     {
         assert!(buffer.len() >= num_words);
         assert!(num_words <= u16::max_value() as usize);
         assert_eq!(buffer.as_ptr().align_offset(mem::size_of::<Word>()), 0);
+        assert_eq!(address as usize % mem::size_of::<PeripheralWord>(), 0);
 
         channel.select_target(handle, &target);
         channel.set_peripheral_address(handle, address);
         channel.set_memory_address(handle, buffer.as_ptr() as u32);
         channel.set_transfer_len(handle, num_words as u16);
-        channel.configure::<Word>(handle, priority.0, dir.0, circular);
+        channel.configure::<Word, PeripheralWord>(handle, priority.0, dir.0, circular);
 
         Transfer {
             res: TransferResources {
@@ -147,6 +197,116 @@ where
         }
     }
 
+    /// Prepares a transfer from `buffer` to the data register of `target`
+    ///
+    /// The memory word size (`MSIZE`) is `Word`, the element type of
+    /// `buffer`, while the peripheral word size (`PSIZE`) is the width of the
+    /// data register, [`DataRegister::Word`]. The sizes are independent of
+    /// each other, as the DMA supports every combination of them. The
+    /// memory address is incremented by one `Word` per transfer, the
+    /// peripheral address stays fixed. As the peripheral word size is taken
+    /// from the target, the data register is never accessed with a width it
+    /// doesn't support.
+    ///
+    /// The DMA doesn't pack or unpack data. Every transfer moves one word
+    /// from the buffer into the data register:
+    /// - If `Word` is larger than the data register, only its least
+    ///   significant bits are written.
+    /// - If `Word` is smaller, it is zero-extended.
+    ///
+    /// For example, a `u8` buffer written to a 16-bit data register writes
+    /// one zero-extended byte per transfer, not two bytes packed into a
+    /// half-word.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the length of `buffer` is larger than `u16::max_value()`.
+    pub fn memory_to_peripheral<Word>(
+        handle: &mut Handle,
+        target: T,
+        channel: C,
+        buffer: Pin<B>,
+        priority: Priority,
+        circular: bool,
+    ) -> Self
+    where
+        T: DataRegister<C>,
+        B: Deref + 'static,
+        B::Target: AsSlice<Element = Word>,
+        Word: SupportedWordSize,
+    {
+        let num_words = buffer.as_slice().len();
+        let address = target.address();
+
+        // Safe, because the trait bounds of this method guarantee that the
+        // buffer can be read from, and that the address is the data register
+        // of the target.
+        unsafe {
+            Self::new_with_peripheral_size::<Word, T::Word>(
+                handle,
+                target,
+                channel,
+                buffer,
+                num_words,
+                address,
+                priority,
+                Direction::memory_to_peripheral(),
+                circular,
+            )
+        }
+    }
+
+    /// Prepares a transfer from the data register of `target` to `buffer`
+    ///
+    /// Like [`Transfer::memory_to_peripheral`], the memory word size
+    /// (`MSIZE`) is `Word` and the peripheral word size (`PSIZE`) is the width
+    /// of the data register. Every transfer moves one word from the data
+    /// register into the buffer:
+    /// - If `Word` is smaller than the data register, only the least
+    ///   significant bits of the register are stored.
+    /// - If `Word` is larger, the value is zero-extended.
+    ///
+    /// For example, reading a 32-bit data register into a `u16` buffer stores
+    /// the lower 16 bits of every value.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the length of `buffer` is larger than `u16::max_value()`.
+    pub fn peripheral_to_memory<Word>(
+        handle: &mut Handle,
+        target: T,
+        channel: C,
+        buffer: Pin<B>,
+        priority: Priority,
+        circular: bool,
+    ) -> Self
+    where
+        T: DataRegister<C>,
+        B: DerefMut + 'static,
+        B::Target: AsMutSlice<Element = Word>,
+        Word: SupportedWordSize,
+    {
+        let num_words = buffer.as_slice().len();
+        let address = target.address();
+
+        // Safe, because the trait bounds of this method guarantee that the
+        // buffer can be written to, and that the address is the data register
+        // of the target.
+        unsafe {
+            Self::new_with_peripheral_size::<Word, T::Word>(
+                handle,
+                target,
+                channel,
+                buffer,
+                num_words,
+                address,
+                priority,
+                Direction::peripheral_to_memory(),
+                circular,
+            )
+        }
+    }
+
     /// Enables the provided interrupts
     ///
     /// This setting only affects this transfer. It doesn't affect transfer on
@@ -281,9 +441,15 @@ pub trait Channel: Sized {
     fn set_peripheral_address(&self, _: &mut Handle, address: u32);
     fn set_memory_address(&self, _: &mut Handle, address: u32);
     fn set_transfer_len(&self, _: &mut Handle, len: u16);
-    fn configure<Word>(&self, _: &mut Handle, priority: cr::PL_A, dir: cr::DIR_A, circular: bool)
-    where
-        Word: SupportedWordSize;
+    fn configure<Word, PeripheralWord>(
+        &self,
+        _: &mut Handle,
+        priority: cr::PL_A,
+        dir: cr::DIR_A,
+        circular: bool,
+    ) where
+        Word: SupportedWordSize,
+        PeripheralWord: SupportedWordSize;
     fn enable_interrupts(&self, interrupts: Interrupts);
     fn start(&self);
     fn is_active(&self) -> bool;
@@ -355,20 +521,22 @@ macro_rules! impl_channel {
                     handle.dma.$chfield.ndtr.write(|w| w.ndt().bits(len));
                 }
 
-                fn configure<Word>(&self,
+                fn configure<Word, PeripheralWord>(&self,
                     handle:   &mut Handle,
                     priority: cr::PL_A,
                     dir:      cr::DIR_A,
                     circular: bool,
                 )
-                    where Word: SupportedWordSize
+                    where
+                        Word:           SupportedWordSize,
+                        PeripheralWord: SupportedWordSize,
                 {
                     handle.dma.$chfield.cr.write(|w| {
                         w
                             // Word size in memory
                             .msize().variant(Word::size())
                             // Word size in peripheral
-                            .psize().variant(Word::size())
+                            .psize().variant(PeripheralWord::size())
                             // Memory-to-memory mode disabled
                             .mem2mem().disabled()
                             // Priority level
@@ -515,6 +683,23 @@ impl_target!(
     adc::DmaToken, Channel2, 0;
 );
 
+impl_target!(
+    // TIM2
+    pwm::UpdateToken<TIM2>, Channel2, 8;
+    pwm::CaptureToken<TIM2, pwm::C1>, Channel5, 8;
+    pwm::CaptureToken<TIM2, pwm::C2>, Channel3, 8;
+    pwm::CaptureToken<TIM2, pwm::C2>, Channel7, 8;
+    pwm::CaptureToken<TIM2, pwm::C3>, Channel1, 8;
+    pwm::CaptureToken<TIM2, pwm::C4>, Channel4, 8;
+    pwm::CaptureToken<TIM2, pwm::C4>, Channel7, 8;
+
+    // TIM3
+    pwm::UpdateToken<TIM3>, Channel3, 10;
+    pwm::CaptureToken<TIM3, pwm::C1>, Channel5, 10;
+    pwm::CaptureToken<TIM3, pwm::C3>, Channel2, 10;
+    pwm::CaptureToken<TIM3, pwm::C4>, Channel3, 10;
+);
+
 #[cfg(any(feature = "io-STM32L051", feature = "io-STM32L071"))]
 impl_target!(
     // USART1
@@ -587,6 +772,71 @@ impl_target!(
     spi::Rx<SPI2>, Channel6, 2;
 );
 
+/// A DMA target, whose data is transferred through a single data register
+///
+/// Used by [`Transfer::memory_to_peripheral`] and
+/// [`Transfer::peripheral_to_memory`].
+pub trait DataRegister<Channel>: Target<Channel> {
+    /// Width of the data register, which determines the peripheral word size
+    type Word: SupportedWordSize;
+
+    /// Returns the address of the data register
+    fn address(&self) -> u32;
+}
+
+macro_rules! impl_data_register {
+    ($($target:ty, $word:ty, $register:expr;)*) => {
+        $(
+            impl<C> DataRegister<C> for $target
+            where
+                $target: Target<C>,
+            {
+                type Word = $word;
+
+                fn address(&self) -> u32 {
+                    // Safe, because we're only taking the address of a
+                    // register.
+                    unsafe { &$register as *const _ as u32 }
+                }
+            }
+        )*
+    }
+}
+
+// The ADC registers must be accessed by words, the USART and SPI registers
+// by half-words or words.
+impl_data_register!(
+    adc::DmaToken, u32, (*ADC::ptr()).dr;
+);
+
+#[cfg(any(feature = "io-STM32L051", feature = "io-STM32L071"))]
+impl_data_register!(
+    serial::Tx<USART1>, u16, (*USART1::ptr()).tdr;
+    serial::Rx<USART1>, u16, (*USART1::ptr()).rdr;
+);
+
+#[cfg(any(
+    feature = "io-STM32L021",
+    feature = "io-STM32L031",
+    feature = "io-STM32L051",
+    feature = "io-STM32L071",
+))]
+impl_data_register!(
+    serial::Tx<USART2>, u16, (*USART2::ptr()).tdr;
+    serial::Rx<USART2>, u16, (*USART2::ptr()).rdr;
+);
+
+impl_data_register!(
+    spi::Tx<SPI1>, u16, (*SPI1::ptr()).dr;
+    spi::Rx<SPI1>, u16, (*SPI1::ptr()).dr;
+);
+
+#[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
+impl_data_register!(
+    spi::Tx<SPI2>, u16, (*SPI2::ptr()).dr;
+    spi::Rx<SPI2>, u16, (*SPI2::ptr()).dr;
+);
+
 /// Indicates that a DMA transfer is ready
 pub struct Ready;
 
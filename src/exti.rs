@@ -2,6 +2,8 @@
 //!
 //! For convenience, this module reexports the EXTI peripheral from the PAC.
 
+use core::marker::PhantomData;
+
 use crate::pac::EXTI;
 use crate::pwr::PowerMode;
 use crate::syscfg::SYSCFG;
@@ -219,6 +221,35 @@ impl Exti {
         pr & bm != 0
     }
 
+    /// Returns an iterator over all lines of type `L` that are pending.
+    ///
+    /// `EXTI_PR` is read only once, when this method is called. Only lines
+    /// that are unmasked in `EXTI_IMR` are returned. Each line is unpended
+    /// when the iterator yields it.
+    ///
+    /// This allows a single interrupt handler to serve many lines (for
+    /// example, all GPIO lines sharing `EXTI4_15`) without missing edges that
+    /// occur on several lines at the same time:
+    ///
+    /// ``` ignore
+    /// for line in Exti::pending_lines::<GpioLine>() {
+    ///     match line.raw_line() {
+    ///         5 => { /* ... */ }
+    ///         _ => { /* ... */ }
+    ///     }
+    /// }
+    /// ```
+    pub fn pending_lines<L: ExtiLine>() -> PendingLines<L> {
+        // Safety: These are reads without side effects that cannot be
+        // interrupted.
+        let pending = unsafe { (*EXTI::ptr()).pr.read().bits() & (*EXTI::ptr()).imr.read().bits() };
+
+        PendingLines {
+            pending,
+            _line: PhantomData,
+        }
+    }
+
     /// Enters a low-power mode until an interrupt occurs.
     ///
     /// Please note that this method will return after _any_ interrupt that can
@@ -248,6 +279,36 @@ impl Exti {
     }
 }
 
+/// Iterator over pending EXTI lines
+///
+/// Created by [`Exti::pending_lines`].
+pub struct PendingLines<L> {
+    pending: u32,
+    _line: PhantomData<L>,
+}
+
+impl<L: ExtiLine> Iterator for PendingLines<L> {
+    type Item = L;
+
+    fn next(&mut self) -> Option<L> {
+        while self.pending != 0 {
+            let raw = self.pending.trailing_zeros() as u8;
+            self.pending &= !(1 << raw);
+
+            if let Some(line) = L::from_raw_line(raw) {
+                // Safety: This is a "clear by writing 1" register, and we only
+                // clear the bit of a valid line.
+                unsafe {
+                    (*EXTI::ptr()).pr.write(|w| w.bits(1 << raw));
+                }
+                return Some(line);
+            }
+        }
+
+        None
+    }
+}
+
 mod sealed {
     pub trait Sealed {}
 
@@ -104,6 +104,14 @@ impl FLASH {
             .modify(|_, w| w.latency().variant(wait_states));
     }
 
+    /// Powers the flash memory down in Sleep mode (`ACR.SLEEP_PD`)
+    ///
+    /// This saves power in Sleep and low-power sleep mode, but the flash
+    /// memory takes some time to power up again when the core wakes up.
+    pub fn set_power_down_in_sleep(&mut self, enabled: bool) {
+        self.flash.acr.modify(|_, w| w.sleep_pd().bit(enabled));
+    }
+
     /// Erases a page of flash memory
     ///
     /// Attention: You must make sure that your program is not executed from the
@@ -1,4 +1,31 @@
 //! General Purpose Input / Output
+//!
+//! # Pin ownership
+//!
+//! Each pin is a distinct type that implements neither `Clone` nor `Copy`, and
+//! can only be obtained once, by splitting its GPIO port. Every peripheral API
+//! in this crate that drives a pin (PWM, SPI, I2C, Serial, DAC, encoder, MCO,
+//! ...) takes that pin by value. Assigning the same pin to two peripherals is
+//! therefore a compile-time error:
+//!
+//! ``` ignore
+//! let pwm = pwm::Timer::new(dp.TIM2, 10_000.hz(), &mut rcc);
+//! let pwm = pwm.channel1.assign(gpioa.pa0);
+//!
+//! // error[E0382]: use of moved value: `gpioa.pa0`
+//! let encoder = dp.TIM2.encoder((gpioa.pa0, gpioa.pa1), mode, arr, &mut rcc);
+//! ```
+//!
+//! APIs that keep the pins return them when the peripheral is released, so
+//! they can be reconfigured and reused (see `into_floating_input`).
+//!
+//! # Analog connections
+//!
+//! Unlike the STM32L1, the STM32L0 has no routing interface (RI) or analog
+//! switch matrix. The analog inputs of the ADC and the comparators are
+//! hard-wired to specific pins. To use a pin as an analog input, switch it to
+//! analog mode (see `into_analog`), then select its channel in the respective
+//! peripheral. For the ADC, the pin types implement its `Channel` trait.
 
 use core::marker::PhantomData;
 
@@ -129,6 +156,173 @@ pub enum Port {
     PH,
 }
 
+impl Port {
+    /// Returns a pointer to the port's register block
+    fn ptr(self) -> *const u32 {
+        use crate::pac::{GPIOA, GPIOB, GPIOC, GPIOD, GPIOE, GPIOH};
+
+        match self {
+            Port::PA => GPIOA::ptr() as *const u32,
+            Port::PB => GPIOB::ptr() as *const u32,
+            Port::PC => GPIOC::ptr() as *const u32,
+            Port::PD => GPIOD::ptr() as *const u32,
+            Port::PE => GPIOE::ptr() as *const u32,
+            Port::PH => GPIOH::ptr() as *const u32,
+        }
+    }
+}
+
+/// GPIO configuration register, see [`atomic_modify`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Register {
+    /// Mode register (`MODER`), 2 bits per pin
+    Moder = 0x00,
+    /// Output type register (`OTYPER`), 1 bit per pin
+    Otyper = 0x04,
+    /// Output speed register (`OSPEEDR`), 2 bits per pin
+    Ospeedr = 0x08,
+    /// Pull-up/pull-down register (`PUPDR`), 2 bits per pin
+    Pupdr = 0x0c,
+    /// Alternate function register for pins 0-7 (`AFRL`), 4 bits per pin
+    Afrl = 0x20,
+    /// Alternate function register for pins 8-15 (`AFRH`), 4 bits per pin
+    Afrh = 0x24,
+}
+
+/// Replaces the bits selected by `mask` in a GPIO configuration register
+///
+/// The configuration registers are shared by all pins of a port, so changing
+/// a pin's configuration requires a read-modify-write. If it is interrupted by
+/// code that modifies the same register, one of the changes is lost. This
+/// performs the read-modify-write within a critical section, which makes it
+/// atomic on the single-core Cortex-M0+.
+///
+/// This is meant for drivers outside of this crate that need to configure
+/// pins in ways not covered by the pin types, for example for peripherals
+/// this crate doesn't support.
+///
+/// # Safety
+///
+/// `mask` must only select bits of pins owned by the caller. Changing the
+/// configuration of other pins can break the guarantees of their type state.
+pub unsafe fn atomic_modify(port: Port, register: Register, mask: u32, bits: u32) {
+    let reg = port.ptr().add(register as usize / 4) as *mut u32;
+
+    cortex_m::interrupt::free(|_| {
+        let value = core::ptr::read_volatile(reg);
+        core::ptr::write_volatile(reg, (value & !mask) | (bits & mask));
+    });
+}
+
+/// Sets and resets several output pins of a port at the same time
+///
+/// The pins selected by `set` are set high, those selected by `reset` are set
+/// low, with a single write to the port's `BSRR` register. If a pin is
+/// selected by both masks, it is set high.
+///
+/// # Safety
+///
+/// `set` and `reset` must only select pins owned by the caller.
+pub unsafe fn atomic_write(port: Port, set: u32, reset: u32) {
+    let bsrr = port.ptr().add(0x18 / 4) as *mut u32;
+    core::ptr::write_volatile(bsrr, (set & 0xffff) | (reset << 16));
+}
+
+/// Output pins that can be written together with other pins of their port
+///
+/// See [`atomic_write`]. This trait is sealed and can't be implemented
+/// outside of this crate.
+pub trait PortOutputPin: sealed::Sealed {
+    /// Returns the port the pin is part of
+    fn port(&self) -> Port;
+
+    /// Returns the pin's number inside its port
+    fn pin_number(&self) -> u8;
+}
+
+/// Implements the embedded-hal 1.0 digital traits for a pin type
+///
+/// The pin type must have an inherent `mask` method, that returns the bit
+/// mask of the pin within its port.
+#[cfg(feature = "embedded-hal-1")]
+macro_rules! impl_eh1 {
+    ($GPIOX:ident, $Pin:ident) => {
+        impl<MODE> embedded_hal_1::digital::ErrorType for $Pin<Output<MODE>> {
+            type Error = core::convert::Infallible;
+        }
+
+        impl<MODE> embedded_hal_1::digital::ErrorType for $Pin<Input<MODE>> {
+            type Error = core::convert::Infallible;
+        }
+
+        impl<MODE> embedded_hal_1::digital::OutputPin for $Pin<Output<MODE>> {
+            fn set_high(&mut self) -> Result<(), Self::Error> {
+                // NOTE(unsafe) atomic write to a stateless register
+                unsafe { (*$GPIOX::ptr()).bsrr.write(|w| w.bits(self.mask())) };
+                Ok(())
+            }
+
+            fn set_low(&mut self) -> Result<(), Self::Error> {
+                // NOTE(unsafe) atomic write to a stateless register
+                unsafe { (*$GPIOX::ptr()).bsrr.write(|w| w.bits(self.mask() << 16)) };
+                Ok(())
+            }
+
+            fn set_state(
+                &mut self,
+                state: embedded_hal_1::digital::PinState,
+            ) -> Result<(), Self::Error> {
+                let bits = match state {
+                    embedded_hal_1::digital::PinState::High => self.mask(),
+                    embedded_hal_1::digital::PinState::Low => self.mask() << 16,
+                };
+                // NOTE(unsafe) atomic write to a stateless register
+                unsafe { (*$GPIOX::ptr()).bsrr.write(|w| w.bits(bits)) };
+                Ok(())
+            }
+        }
+
+        impl<MODE> embedded_hal_1::digital::StatefulOutputPin for $Pin<Output<MODE>> {
+            fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+                let is_set_high = !embedded_hal_1::digital::StatefulOutputPin::is_set_low(self)?;
+                Ok(is_set_high)
+            }
+
+            fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+                // NOTE(unsafe) atomic read with no side effects
+                let is_set_low = unsafe { (*$GPIOX::ptr()).odr.read().bits() & self.mask() == 0 };
+                Ok(is_set_low)
+            }
+        }
+
+        impl<MODE> embedded_hal_1::digital::InputPin for $Pin<Output<MODE>> {
+            fn is_high(&mut self) -> Result<bool, Self::Error> {
+                let is_high = !embedded_hal_1::digital::InputPin::is_low(self)?;
+                Ok(is_high)
+            }
+
+            fn is_low(&mut self) -> Result<bool, Self::Error> {
+                // NOTE(unsafe) atomic read with no side effects
+                let is_low = unsafe { (*$GPIOX::ptr()).idr.read().bits() & self.mask() == 0 };
+                Ok(is_low)
+            }
+        }
+
+        impl<MODE> embedded_hal_1::digital::InputPin for $Pin<Input<MODE>> {
+            fn is_high(&mut self) -> Result<bool, Self::Error> {
+                let is_high = !embedded_hal_1::digital::InputPin::is_low(self)?;
+                Ok(is_high)
+            }
+
+            fn is_low(&mut self) -> Result<bool, Self::Error> {
+                // NOTE(unsafe) atomic read with no side effects
+                let is_low = unsafe { (*$GPIOX::ptr()).idr.read().bits() & self.mask() == 0 };
+                Ok(is_low)
+            }
+        }
+    };
+}
+
 macro_rules! gpio {
     ($GPIOX:ident, $gpiox:ident, $iopxenr:ident, $PXx:ident, [
         $($PXi:ident: ($pxi:ident, $i:expr, $MODE:ty),)+
@@ -137,7 +331,9 @@ macro_rules! gpio {
         pub mod $gpiox {
             use core::marker::PhantomData;
 
-            use crate::hal::digital::v2::{toggleable, InputPin, OutputPin, StatefulOutputPin};
+            use crate::hal::digital::v2::{
+                toggleable, InputPin, OutputPin, PinState, StatefulOutputPin,
+            };
             use crate::pac::$GPIOX;
             use crate::rcc::Rcc;
             use super::{
@@ -176,6 +372,16 @@ macro_rules! gpio {
                 _mode: PhantomData<MODE>,
             }
 
+            #[cfg(feature = "embedded-hal-1")]
+            impl<MODE> $PXx<MODE> {
+                fn mask(&self) -> u32 {
+                    1 << self.i
+                }
+            }
+
+            #[cfg(feature = "embedded-hal-1")]
+            impl_eh1!($GPIOX, $PXx);
+
             impl<MODE> $PXx<MODE> {
                 /// The port this pin is part of.
                 pub const PORT: Port = Port::$PXx;
@@ -191,6 +397,18 @@ macro_rules! gpio {
                 }
             }
 
+            impl<MODE> super::sealed::Sealed for $PXx<Output<MODE>> {}
+
+            impl<MODE> super::PortOutputPin for $PXx<Output<MODE>> {
+                fn port(&self) -> Port {
+                    Port::$PXx
+                }
+
+                fn pin_number(&self) -> u8 {
+                    self.i
+                }
+            }
+
             impl<MODE> OutputPin for $PXx<Output<MODE>> {
                 type Error = void::Void;
 
@@ -205,6 +423,16 @@ macro_rules! gpio {
                     unsafe { (*$GPIOX::ptr()).bsrr.write(|w| w.bits(1 << (self.i + 16))) };
                     Ok(())
                 }
+
+                fn set_state(&mut self, state: PinState) -> Result<(), Self::Error> {
+                    let bit = match state {
+                        PinState::High => self.i,
+                        PinState::Low => self.i + 16,
+                    };
+                    // NOTE(unsafe) atomic write to a stateless register
+                    unsafe { (*$GPIOX::ptr()).bsrr.write(|w| w.bits(1 << bit)) };
+                    Ok(())
+                }
             }
 
             impl<MODE> StatefulOutputPin for $PXx<Output<MODE>> {
@@ -237,6 +465,32 @@ macro_rules! gpio {
                 }
             }
 
+            impl $PXx<Input<PullUp>> {
+                /// Indicates whether the pin is driven low externally,
+                /// against the internal pull-up
+                ///
+                /// A pulled-up input only reads low, if an external driver
+                /// overrides the pull-up. This can be used to detect whether a
+                /// device is connected, or whether a line is shorted.
+                pub fn is_externally_driven(&self) -> bool {
+                    // NOTE(unsafe) atomic read with no side effects
+                    unsafe { (*$GPIOX::ptr()).idr.read().bits() & (1 << self.i) == 0 }
+                }
+            }
+
+            impl $PXx<Input<PullDown>> {
+                /// Indicates whether the pin is driven high externally,
+                /// against the internal pull-down
+                ///
+                /// A pulled-down input only reads high, if an external driver
+                /// overrides the pull-down. This can be used to detect whether
+                /// a device is connected, or whether a line is shorted.
+                pub fn is_externally_driven(&self) -> bool {
+                    // NOTE(unsafe) atomic read with no side effects
+                    unsafe { (*$GPIOX::ptr()).idr.read().bits() & (1 << self.i) != 0 }
+                }
+            }
+
             impl<MODE> InputPin for $PXx<Input<MODE>> {
                 type Error = void::Void;
 
@@ -258,6 +512,16 @@ macro_rules! gpio {
                     _mode: PhantomData<MODE>,
                 }
 
+                #[cfg(feature = "embedded-hal-1")]
+                impl<MODE> $PXi<MODE> {
+                    fn mask(&self) -> u32 {
+                        1 << $i
+                    }
+                }
+
+                #[cfg(feature = "embedded-hal-1")]
+                impl_eh1!($GPIOX, $PXi);
+
                 impl<MODE> $PXi<MODE> {
                     /// The port this pin is part of.
                     pub const PORT: Port = Port::$PXx;
@@ -332,10 +596,16 @@ macro_rules! gpio {
                     }
 
                     /// Configures the pin to operate as a floating input pin.
+                    ///
+                    /// Besides `MODER` and `PUPDR`, this also resets the output type and
+                    /// the alternate function of the pin to their defaults. This makes it
+                    /// suitable for returning a pin that was used by a peripheral to a
+                    /// safe state.
                     pub fn into_floating_input(
                         mut self,
                     ) -> $PXi<Input<Floating>> {
                         self.mode::<Input<Floating>>();
+                        self.reset_output_type_and_alt_mode();
                         $PXi {
                             _mode: PhantomData
                         }
@@ -468,6 +738,26 @@ macro_rules! gpio {
                         self
                     }
 
+                    /// Resets `OTYPER` (push-pull) and `AFR` (AF0) for this pin.
+                    fn reset_output_type_and_alt_mode(&mut self) {
+                        let offset2 = 4 * $i;
+                        unsafe {
+                            &(*$GPIOX::ptr()).otyper.modify(|r, w| {
+                                w.bits(r.bits() & !(0b1 << $i))
+                            });
+                            if offset2 < 32 {
+                                &(*$GPIOX::ptr()).afrl.modify(|r, w| {
+                                    w.bits(r.bits() & !(0b1111 << offset2))
+                                });
+                            } else {
+                                let offset2 = offset2 - 32;
+                                &(*$GPIOX::ptr()).afrh.modify(|r, w| {
+                                    w.bits(r.bits() & !(0b1111 << offset2))
+                                });
+                            }
+                        }
+                    }
+
                     #[allow(dead_code)]
                     pub(crate) fn set_alt_mode(&self, mode: AltMode) {
                         let mode = mode as u32;
@@ -504,6 +794,18 @@ macro_rules! gpio {
                     }
                 }
 
+                impl<MODE> super::sealed::Sealed for $PXi<Output<MODE>> {}
+
+                impl<MODE> super::PortOutputPin for $PXi<Output<MODE>> {
+                    fn port(&self) -> Port {
+                        Port::$PXx
+                    }
+
+                    fn pin_number(&self) -> u8 {
+                        $i
+                    }
+                }
+
                 impl<MODE> OutputPin for $PXi<Output<MODE>> {
                     type Error = void::Void;
 
@@ -518,6 +820,16 @@ macro_rules! gpio {
                         unsafe { (*$GPIOX::ptr()).bsrr.write(|w| w.bits(1 << ($i + 16))) };
                         Ok(())
                     }
+
+                    fn set_state(&mut self, state: PinState) -> Result<(), Self::Error> {
+                        let bit = match state {
+                            PinState::High => $i,
+                            PinState::Low => $i + 16,
+                        };
+                        // NOTE(unsafe) atomic write to a stateless register
+                        unsafe { (*$GPIOX::ptr()).bsrr.write(|w| w.bits(1 << bit)) };
+                        Ok(())
+                    }
                 }
 
                 impl<MODE> StatefulOutputPin for $PXi<Output<MODE>> {
@@ -564,6 +876,34 @@ macro_rules! gpio {
                     }
                 }
 
+                impl $PXi<Input<PullUp>> {
+                    /// Indicates whether the pin is driven low externally,
+                    /// against the internal pull-up
+                    ///
+                    /// A pulled-up input only reads low, if an external driver
+                    /// overrides the pull-up. This can be used to detect
+                    /// whether a device is connected, or whether a line is
+                    /// shorted.
+                    pub fn is_externally_driven(&self) -> bool {
+                        // NOTE(unsafe) atomic read with no side effects
+                        unsafe { (*$GPIOX::ptr()).idr.read().bits() & (1 << $i) == 0 }
+                    }
+                }
+
+                impl $PXi<Input<PullDown>> {
+                    /// Indicates whether the pin is driven high externally,
+                    /// against the internal pull-down
+                    ///
+                    /// A pulled-down input only reads high, if an external
+                    /// driver overrides the pull-down. This can be used to
+                    /// detect whether a device is connected, or whether a line
+                    /// is shorted.
+                    pub fn is_externally_driven(&self) -> bool {
+                        // NOTE(unsafe) atomic read with no side effects
+                        unsafe { (*$GPIOX::ptr()).idr.read().bits() & (1 << $i) != 0 }
+                    }
+                }
+
                 impl<MODE> InputPin for $PXi<Input<MODE>> {
                     type Error = void::Void;
 
@@ -0,0 +1,401 @@
+//! H-bridge control using GPIO pins
+//!
+//! Three drivers are provided:
+//! - [`HBridge`] drives the four switches of a discrete H-bridge directly.
+//! - [`HBridgeInputs`] drives the two inputs of an H-bridge driver IC (like
+//!   the DRV8833 or L9110), which takes care of the individual switches.
+//! - [`AtomicHBridge`] drives either kind of bridge, if all of its pins are
+//!   on the same GPIO port.
+//!
+//! All insert a dead time between changes of the output state: All switches
+//! are turned off first, then, after the dead time has passed, the switches
+//! for the new state are turned on. This prevents shoot-through, even though
+//! the pins can't be switched at exactly the same time.
+//!
+//! # Atomic pin writes
+//!
+//! [`HBridge`] and [`HBridgeInputs`] accept any `OutputPin`, so they write
+//! the pins one after another. Between the writes, the bridge briefly is in
+//! an intermediate state. Switches are only turned off until the bridge
+//! coasts, and only turned on after the dead time, so this never shorts one
+//! side of the bridge. But a driver IC passes through forward or reverse on
+//! its way to brake, for example.
+//!
+//! [`AtomicHBridge`] writes all pins with a single write to the port's
+//! `BSRR` register (see [`gpio::atomic_write`]), so there are no
+//! intermediate states.
+//!
+//! [`gpio::atomic_write`]: ../gpio/fn.atomic_write.html
+
+use crate::gpio::{self, Port, PortOutputPin};
+use crate::hal::digital::v2::OutputPin;
+
+/// State of the bridge output
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    /// All switches off, the load is floating
+    Coast,
+    /// Current flows from side A to side B
+    Forward,
+    /// Current flows from side B to side A
+    Reverse,
+    /// Both low-side switches on, the load is shorted
+    Brake,
+}
+
+/// A discrete H-bridge, driven by four pins
+///
+/// Each pin switches one transistor on when high. Side A consists of
+/// `high_a` and `low_a`, side B of `high_b` and `low_b`.
+pub struct HBridge<HA, LA, HB, LB> {
+    high_a: HA,
+    low_a: LA,
+    high_b: HB,
+    low_b: LB,
+    dead_time: u32,
+    state: State,
+}
+
+impl<HA, LA, HB, LB, E> HBridge<HA, LA, HB, LB>
+where
+    HA: OutputPin<Error = E>,
+    LA: OutputPin<Error = E>,
+    HB: OutputPin<Error = E>,
+    LB: OutputPin<Error = E>,
+{
+    /// Creates a new `HBridge` and switches it to [`State::Coast`]
+    ///
+    /// `dead_time` is the number of core clock cycles to wait between turning
+    /// off the old and turning on the new switches.
+    pub fn new(high_a: HA, low_a: LA, high_b: HB, low_b: LB, dead_time: u32) -> Result<Self, E> {
+        let mut bridge = HBridge {
+            high_a,
+            low_a,
+            high_b,
+            low_b,
+            dead_time,
+            state: State::Coast,
+        };
+        bridge.all_off()?;
+        Ok(bridge)
+    }
+
+    /// Drives current from side A to side B
+    pub fn forward(&mut self) -> Result<(), E> {
+        self.set_state(State::Forward)
+    }
+
+    /// Drives current from side B to side A
+    pub fn reverse(&mut self) -> Result<(), E> {
+        self.set_state(State::Reverse)
+    }
+
+    /// Shorts the load through both low-side switches
+    pub fn brake(&mut self) -> Result<(), E> {
+        self.set_state(State::Brake)
+    }
+
+    /// Turns off all switches
+    pub fn coast(&mut self) -> Result<(), E> {
+        self.set_state(State::Coast)
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Switches to `state`, inserting the dead time if necessary
+    pub fn set_state(&mut self, state: State) -> Result<(), E> {
+        if state == self.state {
+            return Ok(());
+        }
+
+        self.all_off()?;
+        self.state = State::Coast;
+
+        if state == State::Coast {
+            return Ok(());
+        }
+
+        if self.dead_time > 0 {
+            cortex_m::asm::delay(self.dead_time);
+        }
+
+        match state {
+            State::Forward => {
+                self.high_a.set_high()?;
+                self.low_b.set_high()?;
+            }
+            State::Reverse => {
+                self.high_b.set_high()?;
+                self.low_a.set_high()?;
+            }
+            State::Brake => {
+                self.low_a.set_high()?;
+                self.low_b.set_high()?;
+            }
+            State::Coast => {}
+        }
+        self.state = state;
+
+        Ok(())
+    }
+
+    /// Returns the pins
+    pub fn release(self) -> (HA, LA, HB, LB) {
+        (self.high_a, self.low_a, self.high_b, self.low_b)
+    }
+
+    fn all_off(&mut self) -> Result<(), E> {
+        self.high_a.set_low()?;
+        self.high_b.set_low()?;
+        self.low_a.set_low()?;
+        self.low_b.set_low()
+    }
+}
+
+/// An H-bridge driver IC, driven by its two inputs
+///
+/// The inputs are expected to behave like those of the common driver ICs:
+///
+/// | `in1` | `in2` | State   |
+/// |-------|-------|---------|
+/// | low   | low   | Coast   |
+/// | high  | low   | Forward |
+/// | low   | high  | Reverse |
+/// | high  | high  | Brake   |
+pub struct HBridgeInputs<IN1, IN2> {
+    in1: IN1,
+    in2: IN2,
+    dead_time: u32,
+    state: State,
+}
+
+impl<IN1, IN2, E> HBridgeInputs<IN1, IN2>
+where
+    IN1: OutputPin<Error = E>,
+    IN2: OutputPin<Error = E>,
+{
+    /// Creates a new `HBridgeInputs` and switches it to [`State::Coast`]
+    ///
+    /// `dead_time` is the number of core clock cycles to stay in
+    /// [`State::Coast`] when changing between other states.
+    pub fn new(in1: IN1, in2: IN2, dead_time: u32) -> Result<Self, E> {
+        let mut bridge = HBridgeInputs {
+            in1,
+            in2,
+            dead_time,
+            state: State::Coast,
+        };
+        bridge.in1.set_low()?;
+        bridge.in2.set_low()?;
+        Ok(bridge)
+    }
+
+    /// Drives current from output 1 to output 2
+    pub fn forward(&mut self) -> Result<(), E> {
+        self.set_state(State::Forward)
+    }
+
+    /// Drives current from output 2 to output 1
+    pub fn reverse(&mut self) -> Result<(), E> {
+        self.set_state(State::Reverse)
+    }
+
+    /// Shorts the load
+    pub fn brake(&mut self) -> Result<(), E> {
+        self.set_state(State::Brake)
+    }
+
+    /// Lets the load float
+    pub fn coast(&mut self) -> Result<(), E> {
+        self.set_state(State::Coast)
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Switches to `state`, inserting the dead time if necessary
+    pub fn set_state(&mut self, state: State) -> Result<(), E> {
+        if state == self.state {
+            return Ok(());
+        }
+
+        self.in1.set_low()?;
+        self.in2.set_low()?;
+        self.state = State::Coast;
+
+        if state == State::Coast {
+            return Ok(());
+        }
+
+        if self.dead_time > 0 {
+            cortex_m::asm::delay(self.dead_time);
+        }
+
+        match state {
+            State::Forward => self.in1.set_high()?,
+            State::Reverse => self.in2.set_high()?,
+            State::Brake => {
+                self.in1.set_high()?;
+                self.in2.set_high()?;
+            }
+            State::Coast => {}
+        }
+        self.state = state;
+
+        Ok(())
+    }
+
+    /// Returns the pins
+    pub fn release(self) -> (IN1, IN2) {
+        (self.in1, self.in2)
+    }
+}
+
+/// An H-bridge, driven by pins of a single GPIO port
+///
+/// Works like [`HBridge`] or [`HBridgeInputs`], depending on the constructor,
+/// but each change of the pins is done with a single write to the port's
+/// `BSRR` register. All pins that are turned off are turned off at the same
+/// time, and all pins that are turned on are turned on at the same time.
+pub struct AtomicHBridge<P> {
+    pins: P,
+    port: Port,
+    /// Pins to set for each state, indexed by `State as usize`
+    masks: [u32; 4],
+    dead_time: u32,
+    state: State,
+}
+
+impl<HA, LA, HB, LB> AtomicHBridge<(HA, LA, HB, LB)>
+where
+    HA: PortOutputPin,
+    LA: PortOutputPin,
+    HB: PortOutputPin,
+    LB: PortOutputPin,
+{
+    /// Creates a new `AtomicHBridge` for a discrete H-bridge
+    ///
+    /// The pins are used like those of [`HBridge::new`]. The bridge is
+    /// switched to [`State::Coast`].
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the pins are not all on the same port.
+    pub fn new(high_a: HA, low_a: LA, high_b: HB, low_b: LB, dead_time: u32) -> Self {
+        let port = high_a.port();
+        assert!(low_a.port() == port && high_b.port() == port && low_b.port() == port);
+
+        let masks = [
+            0,
+            mask(&high_a) | mask(&low_b),
+            mask(&high_b) | mask(&low_a),
+            mask(&low_a) | mask(&low_b),
+        ];
+        AtomicHBridge::init((high_a, low_a, high_b, low_b), port, masks, dead_time)
+    }
+}
+
+impl<IN1, IN2> AtomicHBridge<(IN1, IN2)>
+where
+    IN1: PortOutputPin,
+    IN2: PortOutputPin,
+{
+    /// Creates a new `AtomicHBridge` for an H-bridge driver IC
+    ///
+    /// The inputs are used like those of [`HBridgeInputs::new`]. The bridge
+    /// is switched to [`State::Coast`].
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the pins are not on the same port.
+    pub fn with_inputs(in1: IN1, in2: IN2, dead_time: u32) -> Self {
+        let port = in1.port();
+        assert!(in2.port() == port);
+
+        let masks = [0, mask(&in1), mask(&in2), mask(&in1) | mask(&in2)];
+        AtomicHBridge::init((in1, in2), port, masks, dead_time)
+    }
+}
+
+impl<P> AtomicHBridge<P> {
+    fn init(pins: P, port: Port, masks: [u32; 4], dead_time: u32) -> Self {
+        let bridge = AtomicHBridge {
+            pins,
+            port,
+            masks,
+            dead_time,
+            state: State::Coast,
+        };
+        bridge.write(0, bridge.all_pins());
+        bridge
+    }
+
+    /// Drives current from side A to side B, or from output 1 to output 2
+    pub fn forward(&mut self) {
+        self.set_state(State::Forward)
+    }
+
+    /// Drives current from side B to side A, or from output 2 to output 1
+    pub fn reverse(&mut self) {
+        self.set_state(State::Reverse)
+    }
+
+    /// Shorts the load
+    pub fn brake(&mut self) {
+        self.set_state(State::Brake)
+    }
+
+    /// Lets the load float
+    pub fn coast(&mut self) {
+        self.set_state(State::Coast)
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Switches to `state`, inserting the dead time if necessary
+    pub fn set_state(&mut self, state: State) {
+        if state == self.state {
+            return;
+        }
+
+        self.write(0, self.all_pins());
+        self.state = State::Coast;
+
+        if state == State::Coast {
+            return;
+        }
+
+        if self.dead_time > 0 {
+            cortex_m::asm::delay(self.dead_time);
+        }
+
+        self.write(self.masks[state as usize], 0);
+        self.state = state;
+    }
+
+    /// Returns the pins
+    pub fn release(self) -> P {
+        self.pins
+    }
+
+    fn all_pins(&self) -> u32 {
+        self.masks.iter().fold(0, |all, mask| all | mask)
+    }
+
+    fn write(&self, set: u32, reset: u32) {
+        // Safe, as the masks only select the pins owned by this bridge
+        unsafe { gpio::atomic_write(self.port, set, reset) }
+    }
+}
+
+fn mask<P: PortOutputPin>(pin: &P) -> u32 {
+    1 << pin.pin_number()
+}
@@ -14,12 +14,15 @@ use crate::pac::i2c1::{
     cr2::{AUTOEND_A, RD_WRN_A},
     RegisterBlock,
 };
-use crate::rcc::Rcc;
+use crate::rcc::{ClockedPeripheral, Rcc};
 use crate::time::Hertz;
 use cast::u8;
 
 // I²C traits
+use crate::hal::blocking::delay::DelayUs;
 use crate::hal::blocking::i2c::{Read, Write, WriteRead};
+use crate::hal::digital::v2::{InputPin, OutputPin};
+use void::Void;
 
 // I/O Imports
 use crate::gpio::{AltMode, OpenDrain, Output};
@@ -59,8 +62,13 @@ pub struct I2c<I2C, SDA, SCL> {
     i2c: I2C,
     sda: SDA,
     scl: SCL,
+    busy_timeout: u32,
+    scl_cycles: u32,
 }
 
+/// Default timeout for the bus to become idle, in SCL periods
+const BUSY_TIMEOUT: u32 = 1000;
+
 impl<I, SDA, SCL> I2c<I, SDA, SCL>
 where
     I: Instance,
@@ -88,7 +96,7 @@ where
         //
         // t_SYNC1 + t_SYNC2 > 4 * t_I2CCLK
         // t_SCL ~= t_SYNC1 + t_SYNC2 + t_SCLL + t_SCLH
-        let i2cclk = rcc.clocks.apb1_clk().0;
+        let i2cclk = i2c.clock_frequency(rcc);
         let ratio = i2cclk / freq - 4;
         let (presc, scll, sclh, sdadel, scldel) = if freq >= 100_000 {
             // fast-mode or fast-mode plus
@@ -154,13 +162,104 @@ where
             w.pe().set_bit()
         });
 
-        I2c { i2c, sda, scl }
+        I2c {
+            i2c,
+            sda,
+            scl,
+            busy_timeout: BUSY_TIMEOUT,
+            scl_cycles: rcc.clocks.sys_clk().0 / freq,
+        }
+    }
+
+    /// Releases a stuck bus, then initializes the I2C peripheral
+    ///
+    /// A device can be left in the middle of a read by a reset of the
+    /// microcontroller, holding SDA low until it has shifted out the rest of
+    /// the byte. Before the peripheral is configured, SCL is clocked up to 9
+    /// times at about 100 kHz, until SDA is released, followed by a STOP
+    /// condition. Then, [`I2c::new`] is called.
+    ///
+    /// During the recovery sequence, both pins are driven as open-drain GPIO
+    /// outputs, which they must already be configured as. Afterwards, they
+    /// are switched to their alternate function as usual.
+    ///
+    /// [`I2c::new`]: #method.new
+    pub fn new_with_bus_recovery(
+        i2c: I,
+        mut sda: SDA,
+        mut scl: SCL,
+        freq: Hertz,
+        rcc: &mut Rcc,
+    ) -> Self
+    where
+        SDA: SDAPin<I> + OutputPin<Error = Void> + InputPin<Error = Void>,
+        SCL: SCLPin<I> + OutputPin<Error = Void>,
+    {
+        // Half a period at 100 kHz
+        let half_period = rcc.clocks.sys_clk().0 / 200_000 + 1;
+
+        let _ = sda.set_high();
+        let _ = scl.set_high();
+        cortex_m::asm::delay(half_period);
+
+        for _ in 0..9 {
+            if let Ok(true) = sda.is_high() {
+                break;
+            }
+
+            let _ = scl.set_low();
+            cortex_m::asm::delay(half_period);
+            let _ = scl.set_high();
+            cortex_m::asm::delay(half_period);
+        }
+
+        // STOP condition: SDA rises while SCL is high
+        let _ = scl.set_low();
+        let _ = sda.set_low();
+        cortex_m::asm::delay(half_period);
+        let _ = scl.set_high();
+        cortex_m::asm::delay(half_period);
+        let _ = sda.set_high();
+        cortex_m::asm::delay(half_period);
+
+        Self::new(i2c, sda, scl, freq, rcc)
     }
 
     pub fn release(self) -> (I, SDA, SCL) {
         (self.i2c, self.sda, self.scl)
     }
 
+    /// Sets how long to wait for the bus to become idle, in SCL periods
+    ///
+    /// Before a transfer is started, the bus must be idle (`ISR.BUSY`), so a
+    /// START condition isn't issued while another master, or a device that
+    /// glitched, is still using it. If the bus doesn't become idle within the
+    /// timeout, the transfer fails with [`Error::Busy`]. The default is 1000
+    /// SCL periods, which is 10 ms at 100 kHz.
+    ///
+    /// [`Error::Busy`]: enum.Error.html#variant.Busy
+    pub fn set_busy_timeout(&mut self, scl_periods: u32) {
+        self.busy_timeout = scl_periods;
+    }
+
+    /// Waits until the bus is idle, or the busy timeout expires
+    fn wait_until_idle(&self) -> Result<(), Error> {
+        let mut periods = self.busy_timeout;
+
+        while self.i2c.isr.read().busy().is_busy() {
+            self.check_errors()?;
+
+            if periods == 0 {
+                return Err(Error::Busy);
+            }
+            periods -= 1;
+
+            cortex_m::asm::delay(self.scl_cycles);
+        }
+
+        Ok(())
+    }
+
     fn check_errors(&self) -> Result<(), Error> {
         let isr = self.i2c.isr.read();
         if isr.berr().bit_is_set() {
@@ -220,6 +319,138 @@ where
         Ok(value)
     }
 
+    /// Writes `bytes` to the 16-bit register `reg` of the device at `addr`
+    ///
+    /// The register address is sent MSB-first, directly followed by `bytes`,
+    /// in a single transfer. This is the addressing scheme used by larger
+    /// EEPROMs (24xx512 and up) and many sensors.
+    pub fn write_u16reg(&mut self, addr: u8, reg: u16, bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() > 255 - 2 {
+            return Err(Error::InvalidLength);
+        }
+
+        // wait for i2c device to be available
+        self.wait_until_idle()?;
+
+        // if the previous write has failed, we need to flush the TX
+        // buffer to prevent sending old data
+        self.i2c.isr.write(|w| w.txe().set_bit());
+
+        self.start_transfer(addr, bytes.len() + 2, RD_WRN_A::WRITE, AUTOEND_A::AUTOMATIC);
+
+        for c in reg.to_be_bytes().iter().chain(bytes) {
+            self.send_byte(*c)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads from the 16-bit register `reg` of the device at `addr`
+    ///
+    /// The register address is sent MSB-first, followed by a repeated start
+    /// and a read that fills `buffer`.
+    pub fn write_read_u16reg(
+        &mut self,
+        addr: u8,
+        reg: u16,
+        buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        if buffer.len() > 255 {
+            return Err(Error::InvalidLength);
+        }
+
+        self.write_read(addr, &reg.to_be_bytes(), buffer)
+    }
+
+    /// Wraps the I2C peripheral, so transfers are retried on transient errors
+    ///
+    /// A transfer that fails with [`Error::Nack`] or
+    /// [`Error::ArbitrationLost`] is retried up to `retries` times. Some
+    /// devices NACK their address while they are busy, for example an EEPROM
+    /// during a write cycle. Before each retry, the wrapper waits for
+    /// `backoff_us` microseconds using `delay`, doubling the delay after
+    /// every attempt. Other errors are returned immediately. If the bus
+    /// doesn't become idle after a failed attempt, [`Error::Busy`] is
+    /// returned instead of retrying.
+    ///
+    /// [`Error::Busy`]: enum.Error.html#variant.Busy
+    /// [`Error::Nack`]: enum.Error.html#variant.Nack
+    /// [`Error::ArbitrationLost`]: enum.Error.html#variant.ArbitrationLost
+    pub fn with_retries<D>(
+        self,
+        retries: u8,
+        delay: D,
+        backoff_us: u32,
+    ) -> RetryingI2c<I, SDA, SCL, D>
+    where
+        D: DelayUs<u32>,
+    {
+        RetryingI2c {
+            i2c: self,
+            delay,
+            retries,
+            backoff_us,
+        }
+    }
+
+    /// Switches the I2C peripheral to slave mode
+    ///
+    /// The peripheral responds to the addresses selected by `config`. See
+    /// [`SlaveConfig`] for details.
+    ///
+    /// [`SlaveConfig`]: struct.SlaveConfig.html
+    pub fn into_slave(self, config: SlaveConfig) -> I2cSlave<I, SDA, SCL> {
+        // The own addresses must be disabled while they are changed
+        self.i2c.oar1.write(|w| w.oa1en().clear_bit());
+        self.i2c.oar2.write(|w| w.oa2en().clear_bit());
+
+        if let Some(address) = config.own_address1 {
+            #[allow(unused_unsafe)]
+            self.i2c.oar1.write(|w| unsafe {
+                match address {
+                    OwnAddress::SevenBit(address) => {
+                        w.oa1().bits(u16::from(address) << 1).oa1mode().clear_bit()
+                    }
+                    OwnAddress::TenBit(address) => w.oa1().bits(address).oa1mode().set_bit(),
+                };
+                w.oa1en().set_bit()
+            });
+        }
+
+        if let Some((address, masked_bits)) = config.own_address2 {
+            #[allow(unused_unsafe)]
+            self.i2c.oar2.write(|w| unsafe {
+                w.oa2().bits(address);
+                w.oa2msk().bits(masked_bits);
+                w.oa2en().set_bit()
+            });
+        }
+
+        self.i2c.icr.write(|w| {
+            w.addrcf().set_bit();
+            w.nackcf().set_bit();
+            w.stopcf().set_bit()
+        });
+
+        I2cSlave { i2c: self }
+    }
+
+    /// Ends a transfer that was aborted by an error
+    ///
+    /// If the transfer wasn't set to end automatically, the bus stays busy
+    /// after a NACK until a STOP condition is generated. Returns
+    /// [`Error::Busy`], if the bus doesn't become idle before the busy
+    /// timeout expires.
+    fn abort_transfer(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+        if self.i2c.isr.read().busy().is_busy() {
+            self.i2c.cr2.modify(|_, w| w.stop().set_bit());
+            result = self.wait_until_idle();
+        }
+        self.i2c.icr.write(|w| w.stopcf().set_bit());
+        result
+    }
+
     #[cfg(feature = "stm32l0x2")]
     pub fn write_all<Channel, Buffer>(
         self,
@@ -381,9 +612,7 @@ where
         let reading = !buffer.is_empty();
 
         // wait for i2c device to be available
-        while self.i2c.isr.read().busy().is_busy() {
-            self.check_errors()?;
-        }
+        self.wait_until_idle()?;
 
         // if we are writing bytes
         if writing {
@@ -452,9 +681,582 @@ where
     }
 }
 
+/// I2C peripheral that retries transfers on transient errors
+///
+/// Created by [`I2c::with_retries`].
+///
+/// [`I2c::with_retries`]: struct.I2c.html#method.with_retries
+pub struct RetryingI2c<I2C, SDA, SCL, D> {
+    i2c: I2c<I2C, SDA, SCL>,
+    delay: D,
+    retries: u8,
+    backoff_us: u32,
+}
+
+impl<I, SDA, SCL, D> RetryingI2c<I, SDA, SCL, D>
+where
+    I: Instance,
+    D: DelayUs<u32>,
+{
+    /// Sets the maximum number of retries
+    pub fn set_retries(&mut self, retries: u8) {
+        self.retries = retries;
+    }
+
+    /// Sets the delay before the first retry, in microseconds
+    pub fn set_backoff_us(&mut self, backoff_us: u32) {
+        self.backoff_us = backoff_us;
+    }
+
+    /// Releases the I2C peripheral and the delay
+    pub fn release(self) -> (I2c<I, SDA, SCL>, D) {
+        (self.i2c, self.delay)
+    }
+
+    fn retry<F>(&mut self, mut transfer: F) -> Result<(), Error>
+    where
+        F: FnMut(&mut I2c<I, SDA, SCL>) -> Result<(), Error>,
+    {
+        let mut backoff_us = self.backoff_us;
+        let mut retries = self.retries;
+
+        loop {
+            match transfer(&mut self.i2c) {
+                Err(Error::Nack) | Err(Error::ArbitrationLost) if retries > 0 => {
+                    retries -= 1;
+                    self.i2c.abort_transfer()?;
+                    self.delay.delay_us(backoff_us);
+                    backoff_us = backoff_us.saturating_mul(2);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<I, SDA, SCL, D> WriteRead for RetryingI2c<I, SDA, SCL, D>
+where
+    I: Instance,
+    D: DelayUs<u32>,
+{
+    type Error = Error;
+
+    fn write_read(&mut self, addr: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.retry(|i2c| i2c.write_read(addr, bytes, buffer))
+    }
+}
+
+impl<I, SDA, SCL, D> Write for RetryingI2c<I, SDA, SCL, D>
+where
+    I: Instance,
+    D: DelayUs<u32>,
+{
+    type Error = Error;
+
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.retry(|i2c| i2c.write(addr, bytes))
+    }
+}
+
+impl<I, SDA, SCL, D> Read for RetryingI2c<I, SDA, SCL, D>
+where
+    I: Instance,
+    D: DelayUs<u32>,
+{
+    type Error = Error;
+
+    fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.retry(|i2c| i2c.read(addr, buffer))
+    }
+}
+
+/// I2C master that is bit-banged on two GPIO pins
+///
+/// Can be used for an additional bus, or on pins that can't be connected to
+/// an I2C peripheral. Both pins must be configured as open-drain outputs,
+/// with pull-up resistors on the bus. The timing is generated using `delay`,
+/// so the actual clock frequency is lower than requested, due to the
+/// overhead of driving the pins.
+///
+/// Clock stretching is supported: after releasing SCL, the master waits for
+/// it to go high, failing with [`Error::Busy`] after the stretch timeout
+/// (see [`BitbangI2c::set_stretch_timeout_us`]). If SDA is low while the
+/// master releases it, the transfer fails with [`Error::ArbitrationLost`].
+///
+/// Reads into an empty buffer fail with [`Error::InvalidLength`], without
+/// starting a transfer. After the address has been acknowledged, the device
+/// drives the first data bit, so the transfer couldn't be stopped reliably.
+///
+/// [`Error::Busy`]: enum.Error.html#variant.Busy
+/// [`Error::ArbitrationLost`]: enum.Error.html#variant.ArbitrationLost
+/// [`Error::InvalidLength`]: enum.Error.html#variant.InvalidLength
+/// [`BitbangI2c::set_stretch_timeout_us`]: #method.set_stretch_timeout_us
+pub struct BitbangI2c<SDA, SCL, D> {
+    sda: SDA,
+    scl: SCL,
+    delay: D,
+    half_period_us: u32,
+    stretch_timeout_us: u32,
+}
+
+impl<SDA, SCL, D> BitbangI2c<SDA, SCL, D>
+where
+    SDA: OutputPin<Error = Void> + InputPin<Error = Void>,
+    SCL: OutputPin<Error = Void> + InputPin<Error = Void>,
+    D: DelayUs<u32>,
+{
+    /// Creates a bit-banged I2C master, clocked at up to `freq`
+    ///
+    /// Releases both lines, so the bus is idle afterwards. The half period of
+    /// the clock is rounded up to whole microseconds, so the actual clock
+    /// never exceeds `freq`.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `freq` is zero.
+    pub fn new(mut sda: SDA, mut scl: SCL, freq: Hertz, delay: D) -> Self {
+        assert!(freq.0 > 0);
+
+        let _ = sda.set_high();
+        let _ = scl.set_high();
+
+        BitbangI2c {
+            sda,
+            scl,
+            delay,
+            half_period_us: (500_000 - 1) / freq.0 + 1,
+            // 10 ms, like the busy timeout of `I2c` at 100 kHz
+            stretch_timeout_us: 10_000,
+        }
+    }
+
+    /// Sets how long a device may stretch the clock, in microseconds
+    pub fn set_stretch_timeout_us(&mut self, timeout_us: u32) {
+        self.stretch_timeout_us = timeout_us;
+    }
+
+    /// Releases the pins and the delay
+    pub fn release(self) -> (SDA, SCL, D) {
+        (self.sda, self.scl, self.delay)
+    }
+
+    fn wait_half_period(&mut self) {
+        self.delay.delay_us(self.half_period_us);
+    }
+
+    /// Releases SCL and waits for devices to stop stretching the clock
+    fn release_scl(&mut self) -> Result<(), Error> {
+        let _ = self.scl.set_high();
+
+        let mut remaining_us = self.stretch_timeout_us;
+        while let Ok(false) = self.scl.is_high() {
+            if remaining_us == 0 {
+                return Err(Error::Busy);
+            }
+            remaining_us -= 1;
+            self.delay.delay_us(1);
+        }
+
+        Ok(())
+    }
+
+    /// Generates a START condition, or a repeated START, if SCL is low
+    fn start(&mut self) -> Result<(), Error> {
+        let _ = self.sda.set_high();
+        self.wait_half_period();
+        self.release_scl()?;
+        if let Ok(false) = self.sda.is_high() {
+            return Err(Error::ArbitrationLost);
+        }
+        self.wait_half_period();
+
+        // START condition: SDA falls while SCL is high
+        let _ = self.sda.set_low();
+        self.wait_half_period();
+        let _ = self.scl.set_low();
+
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Error> {
+        // STOP condition: SDA rises while SCL is high
+        let _ = self.sda.set_low();
+        self.wait_half_period();
+        self.release_scl()?;
+        self.wait_half_period();
+        let _ = self.sda.set_high();
+        self.wait_half_period();
+
+        Ok(())
+    }
+
+    fn write_bit(&mut self, bit: bool) -> Result<(), Error> {
+        if bit {
+            let _ = self.sda.set_high();
+        } else {
+            let _ = self.sda.set_low();
+        }
+        self.wait_half_period();
+        self.release_scl()?;
+
+        // Another master pulls SDA low, while we're sending a 1
+        if bit {
+            if let Ok(false) = self.sda.is_high() {
+                return Err(Error::ArbitrationLost);
+            }
+        }
+
+        self.wait_half_period();
+        let _ = self.scl.set_low();
+
+        Ok(())
+    }
+
+    fn read_bit(&mut self) -> Result<bool, Error> {
+        let _ = self.sda.set_high();
+        self.wait_half_period();
+        self.release_scl()?;
+        let bit = self.sda.is_high().unwrap_or(false);
+        self.wait_half_period();
+        let _ = self.scl.set_low();
+
+        Ok(bit)
+    }
+
+    /// Writes a byte and returns whether it was acknowledged
+    fn write_byte(&mut self, byte: u8) -> Result<bool, Error> {
+        for i in (0..8).rev() {
+            self.write_bit(byte & (1 << i) != 0)?;
+        }
+
+        // ACK: The receiver pulls SDA low
+        Ok(!self.read_bit()?)
+    }
+
+    fn read_byte(&mut self, ack: bool) -> Result<u8, Error> {
+        let mut byte = 0;
+        for _ in 0..8 {
+            byte = (byte << 1) | self.read_bit()? as u8;
+        }
+
+        self.write_bit(!ack)?;
+
+        Ok(byte)
+    }
+
+    /// Sends the address and checks the acknowledge
+    fn address(&mut self, addr: u8, read: bool) -> Result<(), Error> {
+        self.start()?;
+        if !self.write_byte((addr << 1) | read as u8)? {
+            return Err(Error::Nack);
+        }
+
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
+        self.address(addr, false)?;
+        for &byte in bytes {
+            if !self.write_byte(byte)? {
+                return Err(Error::Nack);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_bytes(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        self.address(addr, true)?;
+
+        let len = buffer.len();
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            // NACK the last byte, to tell the device that we're done
+            *byte = self.read_byte(i + 1 < len)?;
+        }
+
+        Ok(())
+    }
+
+    /// Finishes a transfer with a STOP condition, if possible
+    fn finish(&mut self, result: Result<(), Error>) -> Result<(), Error> {
+        match result {
+            // Another master owns the bus, so leave it alone
+            Err(Error::ArbitrationLost) => {
+                let _ = self.sda.set_high();
+                let _ = self.scl.set_high();
+                result
+            }
+            result => {
+                let stop = self.stop();
+                result.and(stop)
+            }
+        }
+    }
+}
+
+impl<SDA, SCL, D> WriteRead for BitbangI2c<SDA, SCL, D>
+where
+    SDA: OutputPin<Error = Void> + InputPin<Error = Void>,
+    SCL: OutputPin<Error = Void> + InputPin<Error = Void>,
+    D: DelayUs<u32>,
+{
+    type Error = Error;
+
+    fn write_read(&mut self, addr: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Self::Error> {
+        if buffer.is_empty() {
+            return Err(Error::InvalidLength);
+        }
+
+        let result = self
+            .write_bytes(addr, bytes)
+            .and_then(|()| self.read_bytes(addr, buffer));
+        self.finish(result)
+    }
+}
+
+impl<SDA, SCL, D> Write for BitbangI2c<SDA, SCL, D>
+where
+    SDA: OutputPin<Error = Void> + InputPin<Error = Void>,
+    SCL: OutputPin<Error = Void> + InputPin<Error = Void>,
+    D: DelayUs<u32>,
+{
+    type Error = Error;
+
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        let result = self.write_bytes(addr, bytes);
+        self.finish(result)
+    }
+}
+
+impl<SDA, SCL, D> Read for BitbangI2c<SDA, SCL, D>
+where
+    SDA: OutputPin<Error = Void> + InputPin<Error = Void>,
+    SCL: OutputPin<Error = Void> + InputPin<Error = Void>,
+    D: DelayUs<u32>,
+{
+    type Error = Error;
+
+    fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        if buffer.is_empty() {
+            return Err(Error::InvalidLength);
+        }
+
+        let result = self.read_bytes(addr, buffer);
+        self.finish(result)
+    }
+}
+
+/// Own address 1 of the I2C slave (`OAR1`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OwnAddress {
+    /// 7-bit address
+    SevenBit(u8),
+    /// 10-bit address
+    TenBit(u16),
+}
+
+/// Slave mode configuration
+///
+/// The slave responds to up to two own addresses at the same time:
+/// - Own address 1 (`OAR1`) is a single 7-bit or 10-bit address.
+/// - Own address 2 (`OAR2`) is a 7-bit address, of which the lowest
+///   `masked_bits` bits (`OA2MSK`) are ignored when matching. A mask of 0
+///   matches the address exactly, a mask of `n` (1 to 6) matches the aligned
+///   block of `2^n` addresses that contains it, and a mask of 7 matches all
+///   addresses, except for the reserved ones (`0b0000xxx` and `0b1111xxx`).
+///
+/// Own address 2 can only match aligned blocks of 2, 4, 8, 16, 32 or 64
+/// addresses. For example, to emulate a 24C16 EEPROM, which occupies the
+/// addresses 0x50 to 0x57, use `own_address2(0x50, 3)`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SlaveConfig {
+    pub own_address1: Option<OwnAddress>,
+    pub own_address2: Option<(u8, u8)>,
+}
+
+impl SlaveConfig {
+    pub fn own_address1(mut self, address: OwnAddress) -> Self {
+        self.own_address1 = Some(address);
+        self
+    }
+
+    /// Sets own address 2 and the number of its low bits that are ignored
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `address` is not a 7-bit address or `masked_bits` is larger
+    /// than 7.
+    pub fn own_address2(mut self, address: u8, masked_bits: u8) -> Self {
+        assert!(address < 0x80);
+        assert!(masked_bits <= 7);
+
+        self.own_address2 = Some((address, masked_bits));
+        self
+    }
+}
+
+/// Direction of a slave transfer, as requested by the master
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlaveDirection {
+    /// The master writes to the slave, which must call [`I2cSlave::receive`]
+    ///
+    /// [`I2cSlave::receive`]: struct.I2cSlave.html#method.receive
+    MasterWrite,
+    /// The master reads from the slave, which must call
+    /// [`I2cSlave::transmit`]
+    ///
+    /// [`I2cSlave::transmit`]: struct.I2cSlave.html#method.transmit
+    MasterRead,
+}
+
+/// An address match of the I2C slave
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressMatch {
+    /// The 7-bit address sent by the master (`ADDCODE`)
+    ///
+    /// For a 10-bit own address 1, this contains the 10-bit header instead
+    /// (`0b11110xx`, where `xx` are the 2 most significant address bits).
+    pub address: u8,
+    pub direction: SlaveDirection,
+}
+
+/// I2C peripheral in slave mode
+///
+/// Created by [`I2c::into_slave`]. The clock is stretched from the address
+/// match until the transfer is handled by [`receive`] or [`transmit`], so
+/// the slave doesn't need to respond immediately.
+///
+/// [`I2c::into_slave`]: struct.I2c.html#method.into_slave
+/// [`receive`]: #method.receive
+/// [`transmit`]: #method.transmit
+pub struct I2cSlave<I2C, SDA, SCL> {
+    i2c: I2c<I2C, SDA, SCL>,
+}
+
+impl<I, SDA, SCL> I2cSlave<I, SDA, SCL>
+where
+    I: Instance,
+{
+    /// Waits for the master to address the slave
+    ///
+    /// Returns the matched address, so a slave with several addresses can
+    /// tell which one the master is talking to.
+    pub fn poll_address(&mut self) -> nb::Result<AddressMatch, Error> {
+        let i2c = &self.i2c.i2c;
+
+        self.i2c.check_errors()?;
+
+        let isr = i2c.isr.read();
+        if isr.addr().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let direction = if isr.dir().bit_is_set() {
+            // Flush the transmit data register, so we don't send a stale byte
+            i2c.isr.write(|w| w.txe().set_bit());
+            SlaveDirection::MasterRead
+        } else {
+            SlaveDirection::MasterWrite
+        };
+
+        Ok(AddressMatch {
+            address: isr.addcode().bits(),
+            direction,
+        })
+    }
+
+    /// Receives the data written by the master into `buffer`
+    ///
+    /// Must be called after [`poll_address`] returned
+    /// [`SlaveDirection::MasterWrite`]. Returns once the master ends the
+    /// transfer, with the number of bytes written to `buffer`. Bytes that
+    /// don't fit into `buffer` are acknowledged and dropped.
+    ///
+    /// [`poll_address`]: #method.poll_address
+    /// [`SlaveDirection::MasterWrite`]: enum.SlaveDirection.html#variant.MasterWrite
+    pub fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let i2c = &self.i2c.i2c;
+
+        i2c.icr.write(|w| w.addrcf().set_bit());
+
+        let mut len = 0;
+        loop {
+            let isr = i2c.isr.read();
+            if isr.rxne().bit_is_set() {
+                let byte = i2c.rxdr.read().rxdata().bits();
+                if let Some(slot) = buffer.get_mut(len) {
+                    *slot = byte;
+                    len += 1;
+                }
+            } else if isr.stopf().bit_is_set() || isr.addr().bit_is_set() {
+                // The transfer ends with a STOP or a repeated START
+                break;
+            } else {
+                self.i2c.check_errors()?;
+            }
+        }
+
+        i2c.icr.write(|w| w.stopcf().set_bit());
+
+        Ok(len)
+    }
+
+    /// Sends `bytes` to the master
+    ///
+    /// Must be called after [`poll_address`] returned
+    /// [`SlaveDirection::MasterRead`]. Returns once the master ends the
+    /// transfer, with the number of bytes from `bytes` that were sent. If the
+    /// master reads more bytes than available, `0xff` is sent.
+    ///
+    /// [`poll_address`]: #method.poll_address
+    /// [`SlaveDirection::MasterRead`]: enum.SlaveDirection.html#variant.MasterRead
+    pub fn transmit(&mut self, bytes: &[u8]) -> Result<usize, Error> {
+        let i2c = &self.i2c.i2c;
+
+        i2c.icr.write(|w| w.addrcf().set_bit());
+
+        let mut len = 0;
+        loop {
+            let isr = i2c.isr.read();
+            if isr.txis().bit_is_set() {
+                let byte = bytes.get(len).copied().unwrap_or(0xff);
+                i2c.txdr.write(|w| w.txdata().bits(byte));
+                len += 1;
+            } else if isr.nackf().bit_is_set() {
+                // The master acknowledges every byte but the last one
+                i2c.icr.write(|w| w.nackcf().set_bit());
+            } else if isr.stopf().bit_is_set() {
+                break;
+            } else {
+                self.i2c.check_errors()?;
+            }
+        }
+
+        if i2c.isr.read().txe().bit_is_clear() {
+            // The last byte was preloaded, but the master didn't read it
+            len -= 1;
+            i2c.isr.write(|w| w.txe().set_bit());
+        }
+        i2c.icr.write(|w| w.stopcf().set_bit());
+
+        Ok(core::cmp::min(len, bytes.len()))
+    }
+
+    /// Disables the own addresses and returns to master mode
+    pub fn into_master(self) -> I2c<I, SDA, SCL> {
+        let i2c = self.i2c;
+
+        i2c.i2c.oar1.write(|w| w.oa1en().clear_bit());
+        i2c.i2c.oar2.write(|w| w.oa2en().clear_bit());
+
+        i2c
+    }
+}
+
 pub trait Instance: Deref<Target = RegisterBlock> {
     fn ptr() -> *const RegisterBlock;
     fn initialize(&self, rcc: &mut Rcc);
+    fn clock_frequency(&self, rcc: &Rcc) -> u32;
 }
 
 // I2C SDA pin
@@ -475,6 +1277,10 @@ pub enum Error {
     PECError,
     BusError,
     ArbitrationLost,
+    /// The requested transfer is longer than 255 bytes
+    InvalidLength,
+    /// The bus didn't become idle before the busy timeout expired
+    Busy,
 }
 
 pub trait I2cExt<I2C> {
@@ -485,7 +1291,7 @@ pub trait I2cExt<I2C> {
 }
 
 macro_rules! i2c {
-    ($I2CX:ident, $i2cxen:ident, $i2crst:ident,
+    ($I2CX:ident, $i2cxen:ident, $clk_sel:expr,
         sda: [ $(($PSDA:ty, $afsda:expr),)+ ],
         scl: [ $(($PSCL:ty, $afscl:expr),)+ ],
     ) => {
@@ -531,8 +1337,17 @@ macro_rules! i2c {
                 rcc.rb.apb1enr.modify(|_, w| w.$i2cxen().set_bit());
 
                 // Reset I2C
-                rcc.rb.apb1rstr.modify(|_, w| w.$i2crst().set_bit());
-                rcc.rb.apb1rstr.modify(|_, w| w.$i2crst().clear_bit());
+                rcc.reset::<$I2CX>();
+            }
+
+            fn clock_frequency(&self, rcc: &Rcc) -> u32 {
+                // Use the kernel clock selected in `RCC_CCIPR`, if this I2C
+                // has one. Otherwise it is clocked from APB1.
+                let clk: Option<ClockedPeripheral> = $clk_sel;
+                match clk {
+                    Some(peripheral) => rcc.peripheral_clk(peripheral).0,
+                    None => rcc.clocks.apb1_clk().0,
+                }
             }
         }
     };
@@ -540,7 +1355,7 @@ macro_rules! i2c {
 
 #[cfg(feature = "io-STM32L021")]
 i2c!(
-    I2C1, i2c1en, i2c1rst,
+    I2C1, i2c1en, Some(ClockedPeripheral::I2C1),
     sda: [
         (PA10<Output<OpenDrain>>, AltMode::AF1),
         (PA13<Output<OpenDrain>>, AltMode::AF3),
@@ -556,7 +1371,7 @@ i2c!(
 
 #[cfg(feature = "io-STM32L031")]
 i2c!(
-    I2C1, i2c1en, i2c1rst,
+    I2C1, i2c1en, Some(ClockedPeripheral::I2C1),
     sda: [
         (PA10<Output<OpenDrain>>, AltMode::AF1),
         (PB7<Output<OpenDrain>>, AltMode::AF1),
@@ -571,7 +1386,7 @@ i2c!(
 
 #[cfg(feature = "io-STM32L051")]
 i2c!(
-    I2C1, i2c1en, i2c1rst,
+    I2C1, i2c1en, Some(ClockedPeripheral::I2C1),
     sda: [
         (PB7<Output<OpenDrain>>, AltMode::AF1),
         (PB9<Output<OpenDrain>>, AltMode::AF4),
@@ -584,7 +1399,7 @@ i2c!(
 
 #[cfg(feature = "io-STM32L051")]
 i2c!(
-    I2C2, i2c2en, i2c2rst,
+    I2C2, i2c2en, None,
     sda: [
         (PB11<Output<OpenDrain>>, AltMode::AF6),
         (PB14<Output<OpenDrain>>, AltMode::AF5),
@@ -597,7 +1412,7 @@ i2c!(
 
 #[cfg(feature = "io-STM32L071")]
 i2c!(
-    I2C1, i2c1en, i2c1rst,
+    I2C1, i2c1en, Some(ClockedPeripheral::I2C1),
     sda: [
         (PA10<Output<OpenDrain>>, AltMode::AF6),
         (PB7<Output<OpenDrain>>, AltMode::AF1),
@@ -612,7 +1427,7 @@ i2c!(
 
 #[cfg(feature = "io-STM32L071")]
 i2c!(
-    I2C2, i2c2en, i2c2rst,
+    I2C2, i2c2en, None,
     sda: [
         (PB11<Output<OpenDrain>>, AltMode::AF6),
         (PB14<Output<OpenDrain>>, AltMode::AF5),
@@ -625,7 +1440,7 @@ i2c!(
 
 #[cfg(feature = "io-STM32L071")]
 i2c!(
-    I2C3, i2c3en, i2c3rst,
+    I2C3, i2c3en, Some(ClockedPeripheral::I2C3),
     sda: [
         (PB4<Output<OpenDrain>>, AltMode::AF7),
         (PC1<Output<OpenDrain>>, AltMode::AF7),
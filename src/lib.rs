@@ -19,6 +19,8 @@ pub use stm32l0::stm32l0x3 as pac;
 pub mod adc;
 pub mod aes;
 pub mod calibration;
+#[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
+pub mod dac;
 pub mod delay;
 pub mod dma;
 pub mod encoder;
@@ -34,6 +36,7 @@ pub mod exti;
 ))]
 pub mod flash;
 pub mod gpio;
+pub mod hbridge;
 #[cfg(any(
     feature = "io-STM32L021",
     feature = "io-STM32L031",
@@ -49,6 +52,7 @@ pub mod pwr;
 pub mod rcc;
 #[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
 pub mod rng;
+pub mod rotary;
 pub mod rtc;
 #[cfg(any(
     feature = "io-STM32L021",
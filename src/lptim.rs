@@ -4,7 +4,7 @@ use crate::gpio::{self, gpiob};
 use crate::hal;
 use crate::pac::LPTIM;
 use crate::pwr::PWR;
-use crate::rcc::Rcc;
+use crate::rcc::{ClockedPeripheral, PeripheralClockSrc, Rcc};
 use crate::time::{Hertz, MicroSeconds};
 use cast::{u32, u64};
 use core::convert::TryFrom;
@@ -201,45 +201,31 @@ impl<M: CountMode> LpTimer<M> {
         // function can set the `RCC.LSEON` bit, which is otherwise write protected.
         let _ = pwr;
 
-        // Enable selected clock and determine its frequency
-        let input_freq = match clk {
-            ClockSrc::Apb1 => rcc.clocks.apb1_clk(), // always enabled
-            ClockSrc::Lsi => {
-                // Turn on LSI
-                rcc.rb.csr.modify(|_, w| w.lsion().set_bit());
+        // Turn on LSE, if selected. The other clocks are enabled by
+        // `Rcc::set_clock_source` below.
+        if clk == ClockSrc::Lse {
+            rcc.rb.csr.modify(|_, w| w.lseon().set_bit());
 
-                // Wait for LSI to be ready
-                while rcc.rb.csr.read().lsirdy().bit_is_clear() {}
-
-                Hertz(37_000)
-            }
-            ClockSrc::Hsi16 => {
-                // Turn on HSI16
-                rcc.rb.cr.modify(|_, w| w.hsi16on().set_bit());
-
-                // Wait for HSI16 to be ready
-                while rcc.rb.cr.read().hsi16rdyf().bit_is_clear() {}
-
-                Hertz(16_000_000)
-            }
-            ClockSrc::Lse => {
-                // Turn on LSE
-                rcc.rb.csr.modify(|_, w| w.lseon().set_bit());
-
-                // Wait for LSE to be ready
-                while rcc.rb.csr.read().lserdy().bit_is_clear() {}
-
-                Hertz(32_768)
-            }
-        };
+            // Wait for LSE to be ready
+            while rcc.rb.csr.read().lserdy().bit_is_clear() {}
+        }
 
         // Select and enable clock. Right now we only support the internal RCC clocks, but LPTIM can
         // also run as a counter with a dedicated external input.
-        rcc.rb.ccipr.modify(|_, w| w.lptim1sel().bits(clk as u8));
+        rcc.set_clock_source(
+            ClockedPeripheral::LPTIM1,
+            match clk {
+                ClockSrc::Apb1 => PeripheralClockSrc::APB,
+                ClockSrc::Lsi => PeripheralClockSrc::LSI,
+                ClockSrc::Hsi16 => PeripheralClockSrc::HSI16,
+                ClockSrc::Lse => PeripheralClockSrc::LSE,
+            },
+        );
+        let input_freq = rcc.peripheral_clk(ClockedPeripheral::LPTIM1);
+
         rcc.rb.apb1enr.modify(|_, w| w.lptim1en().set_bit());
 
-        rcc.rb.apb1rstr.modify(|_, w| w.lptim1rst().set_bit());
-        rcc.rb.apb1rstr.modify(|_, w| w.lptim1rst().clear_bit());
+        rcc.reset::<LPTIM>();
 
         Self {
             lptim,
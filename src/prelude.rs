@@ -19,6 +19,9 @@ pub use crate::{
     watchdog::{IndependedWatchdogExt as _, WindowWatchdogExt as _},
 };
 
+#[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
+pub use crate::dac::{AnalogOut as _, DacExt as _};
+
 #[cfg(any(
     feature = "io-STM32L021",
     feature = "io-STM32L031",
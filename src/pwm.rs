@@ -1,15 +1,20 @@
 use core::marker::PhantomData;
-use core::ops::Deref;
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+
+use as_slice::{AsMutSlice, AsSlice};
 
 use cortex_m::interrupt;
 
+use crate::dma::{self, Buffer as _};
 use crate::gpio::gpioa::{PA0, PA1, PA2, PA3};
 use crate::gpio::{AltMode, PinMode};
 use crate::hal;
 use crate::pac::{tim2, TIM2, TIM3};
 use crate::rcc::Rcc;
 use crate::time::Hertz;
-use cast::{u16, u32};
+use crate::timer::{compute_psc_arr, TimerStatus};
+use void::Void;
 
 #[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
 use crate::gpio::{
@@ -29,6 +34,13 @@ use crate::gpio::{
     gpioe::{PE10, PE11, PE12, PE3, PE4, PE5, PE6, PE9},
 };
 
+/// A general-purpose timer, used for PWM generation and input capture
+///
+/// The STM32L0 only has general-purpose timers. They lack the break and
+/// dead-time block (`BDTR`) of the advanced-control timers, so dead-time
+/// insertion, break inputs and locking the timer configuration aren't
+/// available. See [`crate::hbridge`] for driving a bridge with a dead time
+/// inserted in software.
 pub struct Timer<I> {
     instance: I,
 
@@ -59,12 +71,22 @@ where
 
     /// Starts the PWM timer
     pub fn start(&mut self) {
-        self.instance.cr1.write(|w| w.cen().set_bit());
+        self.instance.cr1.modify(|_, w| w.cen().set_bit());
     }
 
     /// Stops the PWM timer
     pub fn stop(&mut self) {
-        self.instance.cr1.write(|w| w.cen().clear_bit());
+        self.instance.cr1.modify(|_, w| w.cen().clear_bit());
+    }
+
+    /// Enables or disables the auto-reload preload (`CR1.ARPE`)
+    ///
+    /// With preload enabled, a new auto-reload value only takes effect at the
+    /// next update event. Otherwise, it takes effect immediately, which can
+    /// produce a single period that is too long, if the counter has already
+    /// passed the new value.
+    pub fn set_arr_preload(&mut self, enabled: bool) {
+        self.instance.cr1.modify(|_, w| w.arpe().bit(enabled));
     }
 
     /// Update frequency of the timer
@@ -80,6 +102,153 @@ where
         self.start();
     }
 
+    /// Updates the frequency at the next update event, without stopping
+    ///
+    /// Enables the auto-reload preload (see [`set_arr_preload`]) and writes
+    /// the new prescaler and auto-reload values, which the timer loads at the
+    /// end of the current period. The output keeps running without a glitch.
+    /// As with [`set_frequency`], the duty cycles need to be adjusted to the
+    /// new period.
+    ///
+    /// [`set_arr_preload`]: #method.set_arr_preload
+    /// [`set_frequency`]: #method.set_frequency
+    pub fn set_frequency_preloaded(&mut self, frequency: Hertz, rcc: &Rcc) {
+        self.set_arr_preload(true);
+        let (psc, arr) = get_clock_config(frequency.0, self.instance.clock_frequency(rcc));
+        // The prescaler is always preloaded
+        self.instance.psc.write(|w| w.psc().bits(psc));
+        self.instance.arr.write(|w| w.arr().bits(arr));
+    }
+
+    /// Enables the update interrupt, which occurs at the end of each period
+    pub fn listen(&mut self) {
+        self.instance.dier.modify(|_, w| w.uie().set_bit());
+    }
+
+    /// Disables the update interrupt
+    pub fn unlisten(&mut self) {
+        self.instance.dier.modify(|_, w| w.uie().clear_bit());
+    }
+
+    /// Clears the update interrupt flag
+    pub fn clear_irq(&mut self) {
+        // The flags are cleared by writing 0, and writing 1 has no effect. A
+        // read-modify-write could clear flags set in between.
+        #[allow(unused_unsafe)]
+        self.instance.sr.write(|w| unsafe { w.bits(!1) });
+    }
+
+    /// Returns the status flags, for diagnostics
+    ///
+    /// This can be used to find out why the timer interrupt fires, or whether
+    /// a capture was missed (see [`TimerStatus::overcapture`]).
+    ///
+    /// [`TimerStatus::overcapture`]: ../timer/struct.TimerStatus.html#structfield.overcapture
+    pub fn status(&self) -> TimerStatus {
+        TimerStatus::from_bits(self.instance.sr.read().bits())
+    }
+
+    /// Clears all status flags
+    pub fn clear_flags(&mut self) {
+        // The flags are cleared by writing 0
+        #[allow(unused_unsafe)]
+        self.instance.sr.write(|w| unsafe { w.bits(0) });
+    }
+
+    /// Returns the current counter value
+    ///
+    /// This can be used to measure the elapsed time within a PWM period. Like
+    /// all timers on the STM32L0, TIM2 and TIM3 are 16 bits wide.
+    pub fn counter(&self) -> u16 {
+        self.instance.cnt.read().cnt().bits()
+    }
+
+    /// Resets the counter by generating an update event (`EGR.UG`)
+    pub fn reset_count(&mut self) {
+        self.instance.egr.write(|w| w.ug().set_bit());
+    }
+
+    /// Animates the duty cycles of all four channels from a table, using DMA
+    ///
+    /// Each element of `frames` holds the duty cycles of channel 1 to 4. On
+    /// every update event, the DMA copies the next frame into `CCR1..CCR4`,
+    /// using the DMA burst mode of the timer (`DCR`/`DMAR`), so the CPU isn't
+    /// involved at all. This means the frame rate is the PWM frequency. Repeat
+    /// frames in the table to show them for longer.
+    ///
+    /// If `looping` is `true`, the animation restarts from the first frame
+    /// once the last frame has been shown, until [`stop_animation`] is called.
+    /// Otherwise, the last frame stays active.
+    ///
+    /// Channels that aren't enabled are unaffected by their duty cycle.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `frames` holds more than 16383 frames.
+    ///
+    /// [`stop_animation`]: #method.stop_animation
+    pub fn animate<DmaChan, Buf>(
+        &mut self,
+        dma: &mut dma::Handle,
+        dma_chan: DmaChan,
+        frames: Pin<Buf>,
+        looping: bool,
+    ) -> Animation<I, DmaChan, Buf, dma::Ready>
+    where
+        UpdateToken<I>: dma::Target<DmaChan>,
+        DmaChan: dma::Channel,
+        Buf: Deref + 'static,
+        Buf::Target: AsSlice<Element = [u16; 4]>,
+    {
+        let num_words = frames.as_slice().len() * 4;
+
+        // Start the burst at `CCR1` (offset 0x34, in 32-bit words) and
+        // transfer 4 words per update event.
+        //
+        // Safe, as we're only writing valid bit patterns.
+        #[allow(unused_unsafe)]
+        self.instance
+            .dcr
+            .write(|w| unsafe { w.dba().bits(0x34 / 4).dbl().bits(4 - 1) });
+
+        // Safe, because the trait bounds of this method guarantee that the
+        // frames can be read from. The array elements are stored without any
+        // padding, so the table can be treated as a flat buffer of `u16`.
+        let transfer = unsafe {
+            dma::Transfer::new_with_peripheral_size::<u16, u32>(
+                dma,
+                UpdateToken(PhantomData),
+                dma_chan,
+                Pin::new(dma::PtrBuffer {
+                    ptr: frames.as_slice().as_ptr() as *const u16,
+                    len: num_words,
+                }),
+                num_words,
+                &self.instance.dmar as *const _ as u32,
+                dma::Priority::high(),
+                dma::Direction::memory_to_peripheral(),
+                looping,
+            )
+        };
+
+        self.instance.dier.modify(|_, w| w.ude().set_bit());
+
+        Animation {
+            frames,
+            inner: transfer,
+        }
+    }
+
+    /// Stops an animation started by [`animate`]
+    ///
+    /// This disables the update DMA request (`DIER.UDE`). The duty cycles of
+    /// the last frame that was transferred stay active.
+    ///
+    /// [`animate`]: #method.animate
+    pub fn stop_animation(&mut self) {
+        self.instance.dier.modify(|_, w| w.ude().clear_bit());
+    }
+
     /// Returns the timer, so it can be used by any else
     pub fn free(self) -> I {
         self.instance
@@ -87,10 +256,7 @@ where
 }
 
 fn get_clock_config(freq: u32, clk: u32) -> (u16, u16) {
-    let ticks = clk / freq;
-    let psc = u16((ticks - 1) / (1 << 16)).unwrap();
-    let arr = u16(ticks / u32(psc + 1)).unwrap();
-    (psc, arr)
+    compute_psc_arr(Hertz(clk), Hertz(freq)).expect("Frequency out of range for this timer")
 }
 
 pub trait Instance: Deref<Target = tim2::RegisterBlock> {
@@ -104,9 +270,7 @@ macro_rules! impl_instance {
         $(
             $name:ty,
             $apbXenr:ident,
-            $apbXrstr:ident,
             $timXen:ident,
-            $timXrst:ident,
             $apbX_clk:ident;
         )*
     ) => {
@@ -118,8 +282,7 @@ macro_rules! impl_instance {
 
                 fn enable(&self, rcc: &mut Rcc) {
                     rcc.rb.$apbXenr.modify(|_, w| w.$timXen().set_bit());
-                    rcc.rb.$apbXrstr.modify(|_, w| w.$timXrst().set_bit());
-                    rcc.rb.$apbXrstr.modify(|_, w| w.$timXrst().clear_bit());
+                    rcc.reset::<$name>();
                 }
 
                 fn clock_frequency(&self, rcc: &Rcc) -> u32 {
@@ -131,8 +294,8 @@ macro_rules! impl_instance {
 }
 
 impl_instance!(
-    TIM2, apb1enr, apb1rstr, tim2en, tim2rst, apb1_clk;
-    TIM3, apb1enr, apb1rstr, tim3en, tim3rst, apb1_clk;
+    TIM2, apb1enr, tim2en, apb1_clk;
+    TIM3, apb1enr, tim3en, apb1_clk;
 );
 
 pub trait Channel {
@@ -140,6 +303,74 @@ pub trait Channel {
     fn enable(_: &tim2::RegisterBlock);
     fn get_duty(_: &tim2::RegisterBlock) -> u16;
     fn set_duty(_: &tim2::RegisterBlock, duty: u16);
+    fn set_preload(_: &tim2::RegisterBlock, enabled: bool);
+    fn set_mode(_: &tim2::RegisterBlock, mode: OcMode);
+    fn enable_output(_: &tim2::RegisterBlock);
+    fn set_capture(_: &tim2::RegisterBlock, capture: bool);
+    fn set_filter(_: &tim2::RegisterBlock, filter: InputFilter);
+    fn is_captured(_: &tim2::RegisterBlock) -> bool;
+    fn set_dma(_: &tim2::RegisterBlock, enabled: bool);
+    fn ccr_address(_: &tim2::RegisterBlock) -> u32;
+}
+
+/// Output compare mode of a channel (`OCxM`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OcMode {
+    /// The output is not affected by a compare match
+    Frozen = 0b000,
+    /// Set the output high on a compare match
+    SetOnMatch = 0b001,
+    /// Set the output low on a compare match
+    ClearOnMatch = 0b010,
+    /// Toggle the output on a compare match
+    Toggle = 0b011,
+    /// Force the output low
+    ForceLow = 0b100,
+    /// Force the output high
+    ForceHigh = 0b101,
+}
+
+/// Digital filter of a channel's input (`ICxF`)
+///
+/// An edge is only accepted after the input has been sampled at the same
+/// level `N` times in a row. The sampling frequency is either the timer clock
+/// (`CK_INT`) or derived from `DTS`, which equals the timer clock unless
+/// `CR1.CKD` is set. Longer filters suppress more noise, but also delay the
+/// capture. See the description of `TIMx_CCMR1` in the reference manual.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputFilter {
+    /// No filter, sampling at `DTS`
+    None = 0b0000,
+    /// Sampling at `CK_INT`, `N = 2`
+    CkIntN2 = 0b0001,
+    /// Sampling at `CK_INT`, `N = 4`
+    CkIntN4 = 0b0010,
+    /// Sampling at `CK_INT`, `N = 8`
+    CkIntN8 = 0b0011,
+    /// Sampling at `DTS / 2`, `N = 6`
+    DtsDiv2N6 = 0b0100,
+    /// Sampling at `DTS / 2`, `N = 8`
+    DtsDiv2N8 = 0b0101,
+    /// Sampling at `DTS / 4`, `N = 6`
+    DtsDiv4N6 = 0b0110,
+    /// Sampling at `DTS / 4`, `N = 8`
+    DtsDiv4N8 = 0b0111,
+    /// Sampling at `DTS / 8`, `N = 6`
+    DtsDiv8N6 = 0b1000,
+    /// Sampling at `DTS / 8`, `N = 8`
+    DtsDiv8N8 = 0b1001,
+    /// Sampling at `DTS / 16`, `N = 5`
+    DtsDiv16N5 = 0b1010,
+    /// Sampling at `DTS / 16`, `N = 6`
+    DtsDiv16N6 = 0b1011,
+    /// Sampling at `DTS / 16`, `N = 8`
+    DtsDiv16N8 = 0b1100,
+    /// Sampling at `DTS / 32`, `N = 5`
+    DtsDiv32N5 = 0b1101,
+    /// Sampling at `DTS / 32`, `N = 6`
+    DtsDiv32N6 = 0b1110,
+    /// Sampling at `DTS / 32`, `N = 8`
+    DtsDiv32N8 = 0b1111,
 }
 
 macro_rules! impl_channel {
@@ -150,7 +381,12 @@ macro_rules! impl_channel {
             $ccmr_output:ident,
             $ocxpe:ident,
             $ocxm:ident,
-            $ccrx:ident;
+            $ccrx:ident,
+            $ccmr_input:ident,
+            $ccxs:ident,
+            $icxf:ident,
+            $ccxif:ident,
+            $ccxde:ident;
         )*
     ) => {
         $(
@@ -180,16 +416,61 @@ macro_rules! impl_channel {
                 fn set_duty(tim: &tim2::RegisterBlock, duty: u16) {
                     tim.$ccrx.write(|w| w.ccr().bits(duty.into()));
                 }
+
+                fn set_preload(tim: &tim2::RegisterBlock, enabled: bool) {
+                    tim.$ccmr_output().modify(|_, w| w.$ocxpe().bit(enabled));
+                }
+
+                fn set_mode(tim: &tim2::RegisterBlock, mode: OcMode) {
+                    tim.$ccmr_output().modify(|_, w| {
+                        w.$ocxpe().clear_bit();
+                        w.$ocxm().bits(mode as u8)
+                    });
+                }
+
+                fn enable_output(tim: &tim2::RegisterBlock) {
+                    tim.ccer.modify(|_, w| w.$ccxe().set_bit());
+                }
+
+                fn set_capture(tim: &tim2::RegisterBlock, capture: bool) {
+                    // `CCxS` can only be written while the channel is disabled
+                    tim.ccer.modify(|_, w| w.$ccxe().clear_bit());
+                    // Map the channel's own input to its capture register, or
+                    // configure it as an output
+                    #[allow(unused_unsafe)]
+                    tim.$ccmr_input().modify(|_, w| unsafe {
+                        w.$ccxs().bits(if capture { 0b01 } else { 0b00 })
+                    });
+                }
+
+                fn set_filter(tim: &tim2::RegisterBlock, filter: InputFilter) {
+                    #[allow(unused_unsafe)]
+                    tim.$ccmr_input().modify(|_, w| unsafe {
+                        w.$icxf().bits(filter as u8)
+                    });
+                }
+
+                fn is_captured(tim: &tim2::RegisterBlock) -> bool {
+                    tim.sr.read().$ccxif().bit_is_set()
+                }
+
+                fn set_dma(tim: &tim2::RegisterBlock, enabled: bool) {
+                    tim.dier.modify(|_, w| w.$ccxde().bit(enabled));
+                }
+
+                fn ccr_address(tim: &tim2::RegisterBlock) -> u32 {
+                    &tim.$ccrx as *const _ as u32
+                }
             }
         )*
     }
 }
 
 impl_channel!(
-    C1, cc1e, ccmr1_output, oc1pe, oc1m, ccr1;
-    C2, cc2e, ccmr1_output, oc2pe, oc2m, ccr2;
-    C3, cc3e, ccmr2_output, oc3pe, oc3m, ccr3;
-    C4, cc4e, ccmr2_output, oc4pe, oc4m, ccr4;
+    C1, cc1e, ccmr1_output, oc1pe, oc1m, ccr1, ccmr1_input, cc1s, ic1f, cc1if, cc1de;
+    C2, cc2e, ccmr1_output, oc2pe, oc2m, ccr2, ccmr1_input, cc2s, ic2f, cc2if, cc2de;
+    C3, cc3e, ccmr2_output, oc3pe, oc3m, ccr3, ccmr2_input, cc3s, ic3f, cc3if, cc3de;
+    C4, cc4e, ccmr2_output, oc4pe, oc4m, ccr4, ccmr2_input, cc4s, ic4f, cc4if, cc4de;
 );
 
 pub struct Pwm<I, C, State> {
@@ -207,11 +488,20 @@ impl<I, C> Pwm<I, C, Unassigned> {
         }
     }
 
+    /// Assigns a pin to this channel
+    ///
+    /// The compare value is preloaded (`CCMRx.OCxPE`), so a new duty cycle
+    /// takes effect at the next update event, never in the middle of a
+    /// period. See [`Pwm::set_duty_synced`].
     pub fn assign<P>(self, pin: P) -> Pwm<I, C, Assigned<P>>
     where
         P: Pin<I, C>,
     {
         pin.setup();
+        interrupt::free(|_|
+            // Safe, as the read-modify-write within the critical section
+            C::set_preload(unsafe { &*I::ptr() }, true));
+
         Pwm {
             channel: self.channel,
             timer: self.timer,
@@ -261,7 +551,896 @@ where
     }
 }
 
-pub trait Pin<I, C> {
+impl<I, C, P> Pwm<I, C, Assigned<P>>
+where
+    I: Instance,
+    C: Channel,
+{
+    /// Sets the duty cycle, synchronized to the period boundary
+    ///
+    /// The compare value is preloaded: it's written to the preload register
+    /// and only copied to the active compare register at the next update
+    /// event, which happens when the counter wraps around at the end of the
+    /// current period. The current period completes with the old duty cycle,
+    /// so no period with a partial duty cycle is generated.
+    ///
+    /// Use [`is_duty_latched`] to find out whether the new value has become
+    /// active. This clears the update flag (`SR.UIF`), so don't mix it with
+    /// an update interrupt that relies on that flag.
+    ///
+    /// [`is_duty_latched`]: #method.is_duty_latched
+    pub fn set_duty_synced(&mut self, duty: u16) {
+        interrupt::free(|_| {
+            // Safe, as the read-modify-write within the critical section
+            let tim = unsafe { &*I::ptr() };
+            C::set_preload(tim, true);
+            // Only clear `UIF`, see `Timer::clear_irq`
+            #[allow(unused_unsafe)]
+            tim.sr.write(|w| unsafe { w.bits(!1) });
+            C::set_duty(tim, duty);
+        });
+    }
+
+    /// Indicates whether the duty cycle written by [`set_duty_synced`] has
+    /// become active
+    ///
+    /// Returns `true`, once an update event occurred after the write.
+    ///
+    /// [`set_duty_synced`]: #method.set_duty_synced
+    pub fn is_duty_latched(&self) -> bool {
+        // Safe, as we're only doing an atomic read.
+        let tim = unsafe { &*I::ptr() };
+        tim.sr.read().uif().bit_is_set()
+    }
+
+    /// Switches this channel to output compare mode
+    ///
+    /// The output starts out in [`OcMode::Frozen`] and disabled.
+    pub fn into_output_compare(self) -> OutputCompare<I, C, Assigned<P>> {
+        interrupt::free(|_| {
+            // Safe, as the read-modify-write within the critical section
+            let tim = unsafe { &*I::ptr() };
+            C::disable(tim);
+            C::set_mode(tim, OcMode::Frozen);
+        });
+
+        OutputCompare {
+            channel: self.channel,
+            timer: self.timer,
+            _state: self._state,
+        }
+    }
+
+    /// Switches this channel to input capture mode
+    ///
+    /// The counter is captured on rising edges of the channel's pin. The
+    /// input starts out unfiltered and the capture disabled.
+    pub fn into_input_capture(self) -> InputCapture<I, C, Assigned<P>> {
+        interrupt::free(|_| {
+            // Safe, as the read-modify-write within the critical section
+            let tim = unsafe { &*I::ptr() };
+            C::set_capture(tim, true);
+            C::set_filter(tim, InputFilter::None);
+        });
+
+        InputCapture {
+            channel: self.channel,
+            timer: self.timer,
+            _state: self._state,
+        }
+    }
+}
+
+/// A timer channel in output compare mode
+///
+/// Unlike [`Pwm`], which always uses PWM mode 1, this allows the output to be
+/// set, cleared or toggled when the counter matches the compare value. This
+/// can be used to generate single edges or square waves with an arbitrary
+/// phase.
+pub struct OutputCompare<I, C, State> {
+    channel: PhantomData<C>,
+    timer: PhantomData<I>,
+    _state: State,
+}
+
+impl<I, C, P> OutputCompare<I, C, Assigned<P>>
+where
+    I: Instance,
+    C: Channel,
+{
+    /// Sets what happens to the output on a compare match
+    pub fn set_mode(&mut self, mode: OcMode) {
+        interrupt::free(|_|
+            // Safe, as the read-modify-write within the critical section
+            C::set_mode(unsafe { &*I::ptr() }, mode))
+    }
+
+    /// Enables the output
+    pub fn enable(&mut self) {
+        interrupt::free(|_|
+            // Safe, as the read-modify-write within the critical section
+            C::enable_output(unsafe { &*I::ptr() }))
+    }
+
+    /// Disables the output
+    pub fn disable(&mut self) {
+        interrupt::free(|_|
+            // Safe, as the read-modify-write within the critical section
+            C::disable(unsafe { &*I::ptr() }))
+    }
+
+    /// Returns the compare value
+    pub fn get_compare(&self) -> u16 {
+        // Safe, as we're only doing an atomic read.
+        C::get_duty(unsafe { &*I::ptr() })
+    }
+
+    /// Sets the compare value
+    pub fn set_compare(&mut self, value: u16) {
+        // Safe, as we're only doing an atomic write.
+        C::set_duty(unsafe { &*I::ptr() }, value);
+    }
+
+    /// Switches this channel back to PWM mode
+    ///
+    /// The output is disabled and needs to be enabled again.
+    pub fn into_pwm(mut self) -> Pwm<I, C, Assigned<P>> {
+        self.disable();
+
+        Pwm {
+            channel: self.channel,
+            timer: self.timer,
+            _state: self._state,
+        }
+    }
+}
+
+/// A timer channel in input capture mode
+///
+/// The counter value is latched into the capture register on every rising
+/// edge of the channel's pin, for example to measure the period of a
+/// tachometer signal.
+pub struct InputCapture<I, C, State> {
+    channel: PhantomData<C>,
+    timer: PhantomData<I>,
+    _state: State,
+}
+
+impl<I, C, P> InputCapture<I, C, Assigned<P>>
+where
+    I: Instance,
+    C: Channel,
+{
+    /// Sets the digital filter of the input
+    ///
+    /// Without a filter, a noisy or bouncing signal produces spurious
+    /// captures. See [`InputFilter`] for the available sampling frequencies
+    /// and sample counts.
+    pub fn set_filter(&mut self, filter: InputFilter) {
+        interrupt::free(|_|
+            // Safe, as the read-modify-write within the critical section
+            C::set_filter(unsafe { &*I::ptr() }, filter))
+    }
+
+    /// Enables the capture
+    pub fn enable(&mut self) {
+        interrupt::free(|_|
+            // Safe, as the read-modify-write within the critical section
+            C::enable_output(unsafe { &*I::ptr() }))
+    }
+
+    /// Disables the capture
+    pub fn disable(&mut self) {
+        interrupt::free(|_|
+            // Safe, as the read-modify-write within the critical section
+            C::disable(unsafe { &*I::ptr() }))
+    }
+
+    /// Returns the captured counter value, if an edge has been captured
+    ///
+    /// Reading the captured value clears the capture flag.
+    pub fn capture(&mut self) -> nb::Result<u16, Void> {
+        // Safe, as we're only doing atomic reads.
+        let tim = unsafe { &*I::ptr() };
+
+        if C::is_captured(tim) {
+            Ok(C::get_duty(tim))
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Logs the captured counter values into `buffer`, using DMA
+    ///
+    /// Every capture event makes the DMA copy the captured value into the
+    /// next element of `buffer` (`DIER.CCxDE`), without any CPU involvement.
+    /// The difference between two consecutive values is the time between two
+    /// edges, in timer ticks, modulo the period of the timer. This makes it
+    /// possible to record variable-timing waveforms, like the signal of an IR
+    /// remote control, and decode them later.
+    ///
+    /// The transfer stops once `buffer` is full. The capture DMA request is
+    /// disabled again by [`stop_capture_dma`].
+    ///
+    /// `dma_chan` must be one of the DMA channels that are connected to the
+    /// capture event of this timer channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `buffer` is larger than 65535.
+    ///
+    /// [`stop_capture_dma`]: #method.stop_capture_dma
+    pub fn capture_to_buffer<DmaChan, Buf>(
+        &mut self,
+        dma: &mut dma::Handle,
+        dma_chan: DmaChan,
+        buffer: Pin<Buf>,
+    ) -> dma::Transfer<CaptureToken<I, C>, DmaChan, Buf, dma::Ready>
+    where
+        CaptureToken<I, C>: dma::Target<DmaChan>,
+        DmaChan: dma::Channel,
+        Buf: DerefMut + 'static,
+        Buf::Target: AsMutSlice<Element = u16>,
+    {
+        // Safe, as we're only doing an atomic read-modify-write within the
+        // critical section below, and taking the address of a register.
+        let tim = unsafe { &*I::ptr() };
+
+        let num_words = (*buffer).len();
+        let address = C::ccr_address(tim);
+
+        // Safe, because the trait bounds of this method guarantee that the
+        // buffer can be written to. The capture register is 32 bits wide, but
+        // only its lower 16 bits are used.
+        let transfer = unsafe {
+            dma::Transfer::new_with_peripheral_size::<u16, u32>(
+                dma,
+                CaptureToken(PhantomData),
+                dma_chan,
+                buffer,
+                num_words,
+                address,
+                dma::Priority::high(),
+                dma::Direction::peripheral_to_memory(),
+                false,
+            )
+        };
+
+        interrupt::free(|_| C::set_dma(tim, true));
+
+        transfer
+    }
+
+    /// Disables the capture DMA request
+    pub fn stop_capture_dma(&mut self) {
+        interrupt::free(|_| {
+            // Safe, as the read-modify-write within the critical section
+            let tim = unsafe { &*I::ptr() };
+            C::set_dma(tim, false);
+        });
+    }
+
+    /// Switches this channel back to PWM mode
+    ///
+    /// The output is disabled and needs to be enabled again.
+    pub fn into_pwm(self) -> Pwm<I, C, Assigned<P>> {
+        interrupt::free(|_| {
+            // Safe, as the read-modify-write within the critical section
+            let tim = unsafe { &*I::ptr() };
+            C::set_filter(tim, InputFilter::None);
+            C::set_capture(tim, false);
+            C::set_dma(tim, false);
+        });
+
+        Pwm {
+            channel: self.channel,
+            timer: self.timer,
+            _state: self._state,
+        }
+    }
+
+    /// Captures both edges of the input, to measure period and high time
+    ///
+    /// Uses the PWM input mode of the timer: The capture unit of `partner`
+    /// is connected to this channel's input (`CCxS`), so this channel
+    /// captures rising edges, while `partner` captures falling edges
+    /// (`CCxP`). The rising edge (`TI1FP1` or `TI2FP2`) is selected as the
+    /// trigger input (`SMCR.TS`), in reset mode (`SMCR.SMS`), so the counter
+    /// restarts from 0 on every rising edge. The pin of `partner` stays
+    /// unused.
+    ///
+    /// As the counter is reset by the input, the other channels of this
+    /// timer can't be used for PWM meanwhile. Set the timer frequency low
+    /// enough for the counter not to overflow within one period of the
+    /// input.
+    ///
+    /// The previous slave mode configuration (`SMCR`) is saved, and restored
+    /// by [`BothEdges::into_input_capture`].
+    ///
+    /// The capture is enabled right away. Only available on channels 1 and
+    /// 2, which are partners of each other.
+    pub fn both_edges(self, partner: Pwm<I, C::Partner, Unassigned>) -> BothEdges<I, C, Assigned<P>>
+    where
+        C: BothEdgesChannel,
+    {
+        let smcr = interrupt::free(|_| {
+            // Safe, as the read-modify-write within the critical section
+            let tim = unsafe { &*I::ptr() };
+            let smcr = tim.smcr.read().bits();
+            C::configure_both_edges(tim, true);
+            smcr
+        });
+
+        BothEdges {
+            capture: self,
+            partner,
+            smcr,
+        }
+    }
+}
+
+/// Timer channels that can capture both edges of their input
+///
+/// See [`InputCapture::both_edges`].
+pub trait BothEdgesChannel: Channel {
+    /// The channel that captures the falling edges
+    type Partner: Channel;
+
+    #[doc(hidden)]
+    fn configure_both_edges(_: &tim2::RegisterBlock, enabled: bool);
+}
+
+macro_rules! impl_both_edges_channel {
+    (
+        $(
+            $name:ident,
+            $partner:ident,
+            $ccxs:ident,
+            $ccys:ident,
+            $ccxp:ident,
+            $ccyp:ident,
+            $ccxe:ident,
+            $ccye:ident,
+            $ts:expr;
+        )*
+    ) => {
+        $(
+            impl BothEdgesChannel for $name {
+                type Partner = $partner;
+
+                fn configure_both_edges(tim: &tim2::RegisterBlock, enabled: bool) {
+                    // `CCxS` can only be written while the channels are
+                    // disabled
+                    tim.ccer.modify(|_, w| w.$ccxe().clear_bit().$ccye().clear_bit());
+
+                    // Map the partner's capture unit to this channel's input
+                    // (`0b10`), or configure it as an output again
+                    #[allow(unused_unsafe)]
+                    tim.ccmr1_input().modify(|_, w| unsafe {
+                        w.$ccxs().bits(0b01);
+                        w.$ccys().bits(if enabled { 0b10 } else { 0b00 })
+                    });
+
+                    // Capture rising edges on this channel, falling edges on
+                    // the partner
+                    tim.ccer.modify(|_, w| {
+                        w.$ccxp().clear_bit();
+                        w.$ccyp().bit(enabled);
+                        w.$ccxe().bit(enabled);
+                        w.$ccye().bit(enabled)
+                    });
+
+                    // Reset the counter on rising edges. The previous
+                    // configuration is restored by the caller.
+                    if enabled {
+                        #[allow(unused_unsafe)]
+                        tim.smcr.modify(|_, w| unsafe {
+                            w.ts().bits($ts);
+                            w.sms().bits(0b100)
+                        });
+                    }
+                }
+            }
+        )*
+    }
+}
+
+impl_both_edges_channel!(
+    // TI1FP1
+    C1, C2, cc1s, cc2s, cc1p, cc2p, cc1e, cc2e, 0b101;
+    // TI2FP2
+    C2, C1, cc2s, cc1s, cc2p, cc1p, cc2e, cc1e, 0b110;
+);
+
+/// A timer channel capturing both edges of its input
+///
+/// Created by [`InputCapture::both_edges`].
+pub struct BothEdges<I, C, State>
+where
+    C: BothEdgesChannel,
+{
+    capture: InputCapture<I, C, State>,
+    partner: Pwm<I, C::Partner, Unassigned>,
+    /// Slave mode configuration before the capture was set up
+    smcr: u32,
+}
+
+impl<I, C, P> BothEdges<I, C, Assigned<P>>
+where
+    I: Instance,
+    C: BothEdgesChannel,
+{
+    /// Returns the period and high time of the input, in timer ticks
+    ///
+    /// A new pair is available after every rising edge. The first pair after
+    /// the capture has been enabled is incomplete and should be discarded.
+    /// Reading the captured values clears the capture flag.
+    pub fn capture(&mut self) -> nb::Result<(u16, u16), Void> {
+        // Safe, as we're only doing atomic reads.
+        let tim = unsafe { &*I::ptr() };
+
+        if C::is_captured(tim) {
+            let period = C::get_duty(tim);
+            let high_time = <C::Partner as Channel>::get_duty(tim);
+            Ok((period, high_time))
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Switches back to capturing rising edges only
+    ///
+    /// Returns the partner channel, and restores the slave mode configuration
+    /// (`SMCR`) from before [`InputCapture::both_edges`]. The capture is
+    /// disabled and needs to be enabled again.
+    pub fn into_input_capture(
+        self,
+    ) -> (
+        InputCapture<I, C, Assigned<P>>,
+        Pwm<I, C::Partner, Unassigned>,
+    ) {
+        interrupt::free(|_| {
+            // Safe, as the read-modify-write within the critical section
+            let tim = unsafe { &*I::ptr() };
+            C::configure_both_edges(tim, false);
+            #[allow(unused_unsafe)]
+            tim.smcr.write(|w| unsafe { w.bits(self.smcr) });
+        });
+
+        (self.capture, self.partner)
+    }
+}
+
+/// DMA target for the update event of a timer, see [`Timer::animate`]
+///
+/// This is an implementation detail. It is only public because it leaks out
+/// of a public API in the form of a `where` clause.
+pub struct UpdateToken<I>(PhantomData<I>);
+
+/// An animation of the duty cycles of a timer, see [`Timer::animate`]
+///
+/// Wraps a [`dma::Transfer`], as the DMA transfers single duty cycles, while
+/// the public API works with frames of 4 duty cycles.
+pub struct Animation<I, DmaChan, Buf, State> {
+    frames: Pin<Buf>,
+    inner: dma::Transfer<UpdateToken<I>, DmaChan, dma::PtrBuffer<u16>, State>,
+}
+
+impl<I, DmaChan, Buf> Animation<I, DmaChan, Buf, dma::Ready>
+where
+    UpdateToken<I>: dma::Target<DmaChan>,
+    DmaChan: dma::Channel,
+{
+    /// Enables the provided interrupts
+    ///
+    /// This setting only affects this transfer. It doesn't affect transfer on
+    /// other channels, or subsequent transfers on the same channel.
+    pub fn enable_interrupts(&mut self, interrupts: dma::Interrupts) {
+        self.inner.enable_interrupts(interrupts)
+    }
+
+    /// Starts the animation
+    pub fn start(self) -> Animation<I, DmaChan, Buf, dma::Started> {
+        Animation {
+            frames: self.frames,
+            inner: self.inner.start(),
+        }
+    }
+}
+
+impl<I, DmaChan, Buf> Animation<I, DmaChan, Buf, dma::Started>
+where
+    DmaChan: dma::Channel,
+{
+    /// Indicates whether the animation is still running
+    pub fn is_active(&self) -> bool {
+        self.inner.is_active()
+    }
+
+    /// Waits for the animation to finish and returns the owned resources
+    ///
+    /// This function will busily wait until the last frame has been
+    /// transferred. A looping animation never finishes, so don't call this
+    /// for looping animations.
+    pub fn wait(self) -> dma::TransferResourcesResult<UpdateToken<I>, DmaChan, Buf> {
+        let (res, err) = match self.inner.wait() {
+            Ok(res) => (res, None),
+            Err((res, err)) => (res, Some(err)),
+        };
+
+        let res = dma::TransferResources {
+            target: res.target,
+            channel: res.channel,
+            buffer: self.frames,
+        };
+
+        match err {
+            None => Ok(res),
+            Some(err) => Err((res, err)),
+        }
+    }
+}
+
+/// DMA target for the capture events of a timer channel
+///
+/// This is an implementation detail. It is only public because it leaks out
+/// of a public API in the form of a `where` clause.
+pub struct CaptureToken<I, C>(PhantomData<(I, C)>);
+
+/// Brightness curve used by [`PwmFade`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    /// The duty cycle is proportional to the fade level
+    Linear,
+    /// The duty cycle is the square of the fade level (gamma 2)
+    ///
+    /// The eye perceives brightness roughly logarithmically, so LED fades
+    /// look more even with this curve.
+    Gamma,
+}
+
+/// Fades a PWM output towards a target level in steps
+///
+/// Call [`tick`] from the timer's update interrupt (or any other periodic
+/// interrupt). Each tick moves the output one step closer to the target, so
+/// the fade takes `steps` interrupt periods and doesn't block the main loop.
+///
+/// Levels use the full duty cycle range of the pin. With [`Curve::Gamma`],
+/// they are mapped to duty cycles non-linearly.
+///
+/// [`tick`]: #method.tick
+pub struct PwmFade<P> {
+    pin: P,
+    curve: Curve,
+    from: u16,
+    to: u16,
+    level: u16,
+    steps: u16,
+    step: u16,
+}
+
+impl<P> PwmFade<P>
+where
+    P: hal::PwmPin<Duty = u16>,
+{
+    /// Creates a new `PwmFade` and sets the output to level 0
+    pub fn new(mut pin: P, curve: Curve) -> Self {
+        pin.set_duty(0);
+
+        PwmFade {
+            pin,
+            curve,
+            from: 0,
+            to: 0,
+            level: 0,
+            steps: 0,
+            step: 0,
+        }
+    }
+
+    /// Starts fading from the current level to `target` in `steps` ticks
+    ///
+    /// If `steps` is 0, the target is set right away. `target` is clamped to
+    /// the maximum duty cycle.
+    pub fn start_fade(&mut self, target: u16, steps: u16) {
+        self.from = self.level;
+        self.to = core::cmp::min(target, self.pin.get_max_duty());
+        self.steps = steps;
+        self.step = 0;
+
+        if steps == 0 {
+            self.set_level(self.to);
+        }
+    }
+
+    /// Advances the fade by one step
+    ///
+    /// Returns `true`, while the fade is still in progress.
+    pub fn tick(&mut self) -> bool {
+        if self.step >= self.steps {
+            return false;
+        }
+
+        self.step += 1;
+
+        // Interpolate from the start, so rounding errors don't accumulate. Use
+        // 64 bits, as the product overflows 32 bits for long fades.
+        let from = i64::from(self.from);
+        let delta = i64::from(self.to) - from;
+        let level = from + delta * i64::from(self.step) / i64::from(self.steps);
+        self.set_level(level as u16);
+
+        self.step < self.steps
+    }
+
+    /// Returns the current level
+    pub fn level(&self) -> u16 {
+        self.level
+    }
+
+    /// Returns `true`, if a fade is in progress
+    pub fn is_fading(&self) -> bool {
+        self.step < self.steps
+    }
+
+    /// Releases the PWM pin
+    pub fn release(self) -> P {
+        self.pin
+    }
+
+    fn set_level(&mut self, level: u16) {
+        self.level = level;
+
+        let duty = match self.curve {
+            Curve::Linear => level,
+            Curve::Gamma => {
+                let max = u32::from(self.pin.get_max_duty());
+                if max == 0 {
+                    0
+                } else {
+                    (u32::from(level) * u32::from(level) / max) as u16
+                }
+            }
+        };
+        self.pin.set_duty(duty);
+    }
+}
+
+/// PWM output with dithering, for a finer duty cycle resolution
+///
+/// At high PWM frequencies, the auto-reload value is small, so there are only
+/// a few duty cycle steps. For example, at 20 kHz and a timer clock of 2 MHz,
+/// there are only 100 steps. `DitheredPwm` switches the compare value
+/// between the two steps next to the requested duty cycle from one period to
+/// the next, so the average duty cycle over several periods matches the
+/// requested fraction.
+///
+/// Call [`on_update`] from the timer's update interrupt (see
+/// [`Timer::listen`]). This costs one interrupt per PWM period, which is
+/// 20000 interrupts per second at 20 kHz. Keep the handler short.
+///
+/// The output is only correct on average. The pattern of the two duty cycles
+/// repeats after at most `denominator` periods, so the dithering shows up as
+/// a ripple at down to `frequency / denominator`. For LED dimming, that
+/// should stay well above the flicker fusion rate of about 100 Hz.
+///
+/// [`on_update`]: #method.on_update
+/// [`Timer::listen`]: struct.Timer.html#method.listen
+pub struct DitheredPwm<P> {
+    pin: P,
+    duty: u16,
+    remainder: u32,
+    denominator: u32,
+    error: u32,
+}
+
+impl<P> DitheredPwm<P>
+where
+    P: hal::PwmPin<Duty = u16>,
+{
+    /// Creates a new `DitheredPwm` and sets the duty cycle to 0
+    pub fn new(mut pin: P) -> Self {
+        pin.set_duty(0);
+
+        DitheredPwm {
+            pin,
+            duty: 0,
+            remainder: 0,
+            denominator: 1,
+            error: 0,
+        }
+    }
+
+    /// Sets the duty cycle to `numerator / denominator` of the period
+    ///
+    /// The fraction is clamped to 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `denominator` is 0.
+    pub fn set_duty_fractional(&mut self, numerator: u32, denominator: u32) {
+        assert!(denominator > 0);
+
+        let numerator = core::cmp::min(numerator, denominator);
+
+        // The period is `max_duty + 1` timer ticks long
+        let ticks = u64::from(numerator) * (u64::from(self.pin.get_max_duty()) + 1);
+        self.duty = core::cmp::min(ticks / u64::from(denominator), u64::from(u16::MAX)) as u16;
+        self.remainder = (ticks % u64::from(denominator)) as u32;
+        self.denominator = denominator;
+        self.error = 0;
+
+        self.pin.set_duty(self.duty);
+    }
+
+    /// Selects the compare value for the next period
+    ///
+    /// Must be called from the update interrupt of the PWM timer. The compare
+    /// value is preloaded, so it takes effect at the start of the next period.
+    pub fn on_update(&mut self) {
+        // First-order error diffusion: carry the fractional part over to the
+        // following periods, until it adds up to a full step.
+        self.error += self.remainder;
+        if self.error >= self.denominator {
+            self.error -= self.denominator;
+            self.pin.set_duty(self.duty.saturating_add(1));
+        } else {
+            self.pin.set_duty(self.duty);
+        }
+    }
+
+    /// Releases the PWM pin
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
+/// Controls a hobby servo using a PWM output
+///
+/// The PWM timer should run at the servo's frame rate, usually 50 Hz. The
+/// servo position is set by the pulse width, which is mapped to the duty
+/// cycle using the timer frequency passed to [`Servo::new`].
+///
+/// By default, pulse widths from 1000 µs to 2000 µs are mapped to angles from
+/// 0° to 180°. Servos that use a different range can be calibrated using
+/// [`Servo::set_pulse_range`] and [`Servo::set_angle_range`]. All angles and
+/// pulse widths are clamped to the calibrated range, so the servo is never
+/// driven beyond its end stops.
+pub struct Servo<P> {
+    pin: P,
+    period_us: u32,
+    min_us: u16,
+    max_us: u16,
+    max_angle: u16,
+    pulse_us: u16,
+}
+
+impl<P> Servo<P>
+where
+    P: hal::PwmPin<Duty = u16>,
+{
+    /// Creates a new `Servo`, moves it to the center and enables the output
+    ///
+    /// `frequency` must be the frequency of the PWM timer.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `frequency` is zero or higher than 1 MHz.
+    pub fn new(mut pin: P, frequency: Hertz) -> Self {
+        assert!(frequency.0 > 0 && frequency.0 <= 1_000_000);
+
+        pin.enable();
+
+        let mut servo = Servo {
+            pin,
+            period_us: 1_000_000 / frequency.0,
+            min_us: 1000,
+            max_us: 2000,
+            max_angle: 180,
+            pulse_us: 0,
+        };
+        servo.set_pulse_width(1500);
+        servo
+    }
+
+    /// Sets the pulse widths for the end positions of the servo, in µs
+    ///
+    /// `min_us` corresponds to 0°, `max_us` to the maximum angle. The current
+    /// pulse width is clamped to the new range.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `min_us` is not smaller than `max_us`.
+    pub fn set_pulse_range(&mut self, min_us: u16, max_us: u16) {
+        assert!(min_us < max_us);
+
+        self.min_us = min_us;
+        self.max_us = max_us;
+        self.set_pulse_width(self.pulse_us);
+    }
+
+    /// Sets the angle between the end positions of the servo, in degrees
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `max_angle` is zero.
+    pub fn set_angle_range(&mut self, max_angle: u16) {
+        assert!(max_angle > 0);
+
+        self.max_angle = max_angle;
+    }
+
+    /// Moves the servo to `angle`, in degrees
+    ///
+    /// `angle` is clamped to the calibrated angle range.
+    pub fn set_angle(&mut self, angle: u16) {
+        let angle = u32::from(core::cmp::min(angle, self.max_angle));
+        let range = u32::from(self.max_us - self.min_us);
+        let max_angle = u32::from(self.max_angle);
+
+        // Round to the nearest µs
+        let offset = (angle * range + max_angle / 2) / max_angle;
+        self.set_pulse_width(self.min_us + offset as u16);
+    }
+
+    /// Sets the pulse width, in µs
+    ///
+    /// `pulse_us` is clamped to the calibrated pulse range.
+    pub fn set_pulse_width(&mut self, pulse_us: u16) {
+        let pulse_us = core::cmp::max(self.min_us, core::cmp::min(pulse_us, self.max_us));
+        self.pulse_us = pulse_us;
+
+        // The PWM period is `max_duty + 1` timer ticks long, and the output is
+        // high for `duty` ticks. The product can exceed 32 bits for long
+        // pulses with fine resolution.
+        let ticks = u64::from(self.pin.get_max_duty()) + 1;
+        let period_us = u64::from(self.period_us);
+        let duty = (u64::from(pulse_us) * ticks + period_us / 2) / period_us;
+        self.pin
+            .set_duty(core::cmp::min(duty, u64::from(u16::MAX)) as u16);
+    }
+
+    /// Returns the current pulse width, in µs
+    pub fn pulse_width(&self) -> u16 {
+        self.pulse_us
+    }
+
+    /// Releases the PWM pin
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
+mod sealed {
+    pub trait Sealed<I, C> {}
+}
+
+/// Implemented for all pins that can be used by channel `C` of timer `I`
+///
+/// This trait is sealed and can't be implemented outside of this crate, so
+/// code that is generic over it accepts exactly the pins that are valid for
+/// the channel, while the check still happens at compile time:
+///
+/// ``` ignore
+/// use stm32l0xx_hal::pac::TIM2;
+/// use stm32l0xx_hal::pwm::{self, C1};
+///
+/// struct Led<P> {
+///     pwm: pwm::Pwm<TIM2, C1, pwm::Assigned<P>>,
+/// }
+///
+/// impl<P> Led<P>
+/// where
+///     P: pwm::Pin<TIM2, C1>,
+/// {
+///     fn new(channel: pwm::Pwm<TIM2, C1, pwm::Unassigned>, pin: P) -> Self {
+///         Led {
+///             pwm: channel.assign(pin),
+///         }
+///     }
+/// }
+/// ```
+pub trait Pin<I, C>: sealed::Sealed<I, C> {
     fn setup(&self);
 }
 
@@ -279,6 +1458,8 @@ macro_rules! impl_pin {
     ) => {
         $(
             $(
+                impl<State: PinMode> sealed::Sealed<$instance, $channel> for $name<State> {}
+
                 impl<State: PinMode> Pin<$instance, $channel> for $name<State> {
                     fn setup(&self) {
                         self.set_alt_mode(AltMode::$alternate_function);
@@ -346,3 +1527,119 @@ pub struct Unassigned;
 
 /// Indicates that a PWM channel has been assigned to the given pin
 pub struct Assigned<P>(P);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::U32Ext;
+
+    /// PWM pin that records the duty cycle
+    struct MockPin {
+        max_duty: u16,
+        duty: u16,
+        enabled: bool,
+    }
+
+    impl MockPin {
+        fn new(max_duty: u16) -> Self {
+            MockPin {
+                max_duty,
+                duty: 0,
+                enabled: false,
+            }
+        }
+    }
+
+    impl hal::PwmPin for MockPin {
+        type Duty = u16;
+
+        fn disable(&mut self) {
+            self.enabled = false;
+        }
+
+        fn enable(&mut self) {
+            self.enabled = true;
+        }
+
+        fn get_duty(&self) -> u16 {
+            self.duty
+        }
+
+        fn get_max_duty(&self) -> u16 {
+            self.max_duty
+        }
+
+        fn set_duty(&mut self, duty: u16) {
+            self.duty = duty;
+        }
+    }
+
+    /// Returns a servo at 50 Hz, with one timer tick per µs
+    fn servo() -> Servo<MockPin> {
+        Servo::new(MockPin::new(19_999), 50.hz())
+    }
+
+    #[test]
+    fn servo_starts_centered_and_enabled() {
+        let servo = servo();
+        assert_eq!(servo.pulse_width(), 1500);
+
+        let pin = servo.release();
+        assert!(pin.enabled);
+        assert_eq!(pin.duty, 1500);
+    }
+
+    #[test]
+    fn servo_angle_extremes() {
+        let mut servo = servo();
+
+        servo.set_angle(0);
+        assert_eq!(servo.pulse_width(), 1000);
+        assert_eq!(servo.pin.duty, 1000);
+
+        servo.set_angle(180);
+        assert_eq!(servo.pulse_width(), 2000);
+        assert_eq!(servo.pin.duty, 2000);
+    }
+
+    #[test]
+    fn servo_clamps_to_range() {
+        let mut servo = servo();
+
+        servo.set_angle(200);
+        assert_eq!(servo.pulse_width(), 2000);
+
+        servo.set_pulse_width(500);
+        assert_eq!(servo.pulse_width(), 1000);
+
+        servo.set_pulse_width(3000);
+        assert_eq!(servo.pulse_width(), 2000);
+
+        servo.set_pulse_range(1200, 1800);
+        assert_eq!(servo.pulse_width(), 1800);
+        assert_eq!(servo.pin.duty, 1800);
+    }
+
+    #[test]
+    fn servo_rounds_to_nearest() {
+        let mut servo = servo();
+
+        // 1000 µs / 180° * 1° = 5.56 µs
+        servo.set_angle(1);
+        assert_eq!(servo.pulse_width(), 1006);
+
+        // 1000 ticks per 20 ms: 1010 µs = 50.5 ticks
+        let mut servo = Servo::new(MockPin::new(999), 50.hz());
+        servo.set_pulse_width(1010);
+        assert_eq!(servo.pin.duty, 51);
+    }
+
+    #[test]
+    fn servo_duty_does_not_overflow() {
+        // 65535 µs * 65536 ticks exceeds 32 bits, with the rounding term
+        let mut servo = Servo::new(MockPin::new(u16::MAX), 1.hz());
+        servo.set_pulse_range(1000, u16::MAX);
+        servo.set_pulse_width(u16::MAX);
+        assert_eq!(servo.pin.duty, 4295);
+    }
+}
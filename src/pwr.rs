@@ -2,7 +2,10 @@
 //!
 //! See STM32L0x2 reference manual, chapter 6.
 
-use cortex_m::{asm, peripheral::SCB};
+use cortex_m::{
+    asm,
+    peripheral::{SCB, SYST},
+};
 
 use crate::{
     pac,
@@ -106,8 +109,70 @@ impl PWR {
         SleepMode { pwr: self, scb }
     }
 
+    /// Enters Sleep mode and blocks until the core is woken up
+    ///
+    /// In Sleep mode, only the core clock is stopped. All peripherals keep
+    /// running and any enabled interrupt (or, for [`SleepEntry::Wfe`], any
+    /// event) wakes the core up within a few cycles. This is the lightest
+    /// low-power mode and suited to idle between interrupts. Stop mode (see
+    /// [`PWR::stop_mode`]) saves a lot more power, but stops the clocks of
+    /// most peripherals and takes longer to wake up from.
+    ///
+    /// The SysTick interrupt wakes the core up like any other interrupt. If
+    /// SysTick is configured to interrupt periodically, Sleep mode is left
+    /// after at most one SysTick period, which is often not what's intended.
+    /// Pass `syst` to suspend SysTick while sleeping: Its counter and
+    /// interrupt are disabled before entering Sleep mode and restored after
+    /// waking up. Time spent sleeping isn't counted by SysTick then.
+    ///
+    /// Unlike [`PWR::sleep_mode`], this doesn't require a `PowerMode` value
+    /// to be created first.
+    pub fn enter_sleep(&mut self, scb: &mut SCB, syst: Option<&mut SYST>, entry: SleepEntry) {
+        self.clear_lpsdsr();
+        scb.clear_sleepdeep();
+
+        let syst = syst.map(|syst| {
+            let counter = syst.is_counter_enabled();
+            let interrupt = syst.is_interrupt_enabled();
+            syst.disable_interrupt();
+            syst.disable_counter();
+            (syst, counter, interrupt)
+        });
+
+        asm::dsb();
+        match entry {
+            SleepEntry::Wfi => asm::wfi(),
+            SleepEntry::Wfe => asm::wfe(),
+        }
+
+        if let Some((syst, counter, interrupt)) = syst {
+            if counter {
+                syst.enable_counter();
+            }
+            if interrupt {
+                syst.enable_interrupt();
+            }
+        }
+    }
+
     /// Returns a struct that can be used to enter low-power sleep mode
     ///
+    /// In low-power sleep mode, the core is stopped and the voltage regulator
+    /// is in low-power mode, while all peripherals keep running. This allows,
+    /// for example, LPUART1 to keep receiving while the core idles at minimum
+    /// power.
+    ///
+    /// The low-power regulator can only supply a slow system clock, so the
+    /// same clock constraints as in low-power run mode apply: Peripherals that
+    /// stay active are limited by the system clock as well, unless they are
+    /// clocked from LSE, like LPUART1 can be. The flash memory can
+    /// additionally be powered down while sleeping, using
+    /// `FLASH::set_power_down_in_sleep`. This saves some more power, but
+    /// delays the execution of interrupt handlers after waking up, until the
+    /// flash is ready again.
+    ///
+    /// See [`LowPowerSleepMode`] for how the mode is entered.
+    ///
     /// # Panics
     ///
     /// To enter low-power sleep mode, the system clock frequency should not
@@ -189,6 +254,24 @@ impl VcoreRange {
     }
 }
 
+/// Instruction used to enter Sleep mode
+///
+/// Used as an argument for [`PWR::enter_sleep`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SleepEntry {
+    /// Wait for interrupt (`WFI`)
+    ///
+    /// The core wakes up on any enabled interrupt.
+    Wfi,
+
+    /// Wait for event (`WFE`)
+    ///
+    /// The core wakes up on any event, including enabled interrupts and, if
+    /// `SEVONPEND` is set, pending interrupts that are disabled. Returns
+    /// immediately, if an event occurred since the last `WFE`.
+    Wfe,
+}
+
 /// Implemented for all low-power modes
 pub trait PowerMode {
     /// Enters the low-power mode
@@ -228,6 +311,11 @@ impl PowerMode for SleepMode<'_> {
 /// the microcontroller up again. Please make sure to configure an interrupt, or
 /// it could block forever.
 ///
+/// Low-power sleep mode is entered from low-power run mode (see
+/// [`PWR::enter_low_power_run_mode`]), if it is active. Otherwise, Vcore is
+/// switched to range 2 and the regulator to low-power mode first, as
+/// low-power run mode would, and both are restored after waking up.
+///
 /// Please note that entering low-power sleep mode may change the SCB
 /// configuration.
 pub struct LowPowerSleepMode<'r> {
@@ -237,19 +325,27 @@ pub struct LowPowerSleepMode<'r> {
 
 impl PowerMode for LowPowerSleepMode<'_> {
     fn enter(&mut self) {
+        // In low-power run mode, Vcore and the regulator are already set up
+        // correctly, and must stay that way after waking up.
+        let low_power_run = self.pwr.0.cr.read().lprun().bit_is_set();
+
         // Switch Vcore to range 2. This is required to enter low-power sleep
         // mode, according to the reference manual, section 6.3.8.
         let old_vcore = self.pwr.get_vcore_range();
-        self.pwr.switch_vcore_range(VcoreRange::Range2);
-
-        self.pwr.set_lpsdsr();
+        if !low_power_run {
+            self.pwr.switch_vcore_range(VcoreRange::Range2);
+            self.pwr.set_lpsdsr();
+        }
         self.scb.clear_sleepdeep();
 
         asm::dsb();
         asm::wfi();
 
-        // Switch back to previous voltage range.
-        self.pwr.switch_vcore_range(old_vcore);
+        // Switch back to the main regulator and the previous voltage range.
+        if !low_power_run {
+            self.pwr.clear_lpsdsr();
+            self.pwr.switch_vcore_range(old_vcore);
+        }
     }
 }
 
@@ -1,8 +1,9 @@
 use crate::mco;
 use crate::pac::rcc::cfgr::{MCOPRE_A, MCOSEL_A};
-use crate::pac::RCC;
+use crate::pac::{self, RCC, TIM21};
 use crate::pwr::PWR;
 use crate::time::{Hertz, U32Ext};
+use cortex_m::peripheral::{syst::SystClkSource, SYST};
 
 #[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
 use crate::{pac::CRS, syscfg::SYSCFG};
@@ -100,6 +101,65 @@ pub enum PLLSource {
 /// HSI speed
 pub const HSI_FREQ: u32 = 16_000_000;
 
+/// LSE speed
+pub const LSE_FREQ: u32 = 32_768;
+
+/// LSI speed (typical)
+pub const LSI_FREQ: u32 = 37_000;
+
+/// Peripherals whose kernel clock can be selected independently
+///
+/// These map to the `*SEL` fields in the `RCC_CCIPR` register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockedPeripheral {
+    USART1,
+    USART2,
+    LPUART1,
+    I2C1,
+    I2C3,
+    LPTIM1,
+}
+
+/// Kernel clock source of a [`ClockedPeripheral`]
+///
+/// Not every source is available for every peripheral:
+///
+/// - `SYSCLK` can't be selected for LPTIM1.
+/// - `LSE` can't be selected for I2C1 and I2C3.
+/// - `LSI` can only be selected for LPTIM1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeripheralClockSrc {
+    /// The APB clock of the peripheral (reset value)
+    APB,
+    /// The system clock
+    SYSCLK,
+    /// The internal 16 MHz oscillator
+    HSI16,
+    /// The external 32.768 kHz oscillator
+    LSE,
+    /// The internal ~37 kHz oscillator
+    LSI,
+}
+
+/// Clock signal that can be measured using [`Rcc::measure_clock`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeasuredClock {
+    /// Signal on the TIM21_CH1 pin
+    ///
+    /// The pin must be put into the right alternate function by the caller.
+    Gpio,
+    /// HSE, divided by the RTC prescaler (`RTCPRE`)
+    ///
+    /// The prescaler is accounted for, so the HSE frequency is returned.
+    HseRtc,
+    /// The multi-speed internal oscillator
+    Msi,
+    /// The external 32.768 kHz oscillator
+    Lse,
+    /// The internal ~37 kHz oscillator
+    Lsi,
+}
+
 /// Clocks configutation
 pub struct Config {
     mux: ClockSrc,
@@ -189,6 +249,86 @@ impl Config {
     }
 }
 
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Peripherals that can be reset through RCC, see [`Rcc::reset`]
+///
+/// This trait is sealed and can't be implemented outside of this crate.
+pub trait Reset: sealed::Sealed {
+    #[doc(hidden)]
+    fn reset(rcc: &mut Rcc);
+}
+
+macro_rules! impl_reset {
+    ($($(#[$meta:meta])* $PERIPH:ident: ($rstr:ident, $periphrst:ident),)+) => {
+        $(
+            $(#[$meta])*
+            impl sealed::Sealed for pac::$PERIPH {}
+
+            $(#[$meta])*
+            impl Reset for pac::$PERIPH {
+                fn reset(rcc: &mut Rcc) {
+                    rcc.rb.$rstr.modify(|_, w| w.$periphrst().set_bit());
+                    rcc.rb.$rstr.modify(|_, w| w.$periphrst().clear_bit());
+                }
+            }
+        )+
+    };
+}
+
+impl_reset! {
+    ADC: (apb2rstr, adcrst),
+    LPTIM: (apb1rstr, lptim1rst),
+    SPI1: (apb2rstr, spi1rst),
+    SYSCFG: (apb2rstr, syscfgrst),
+    TIM2: (apb1rstr, tim2rst),
+    TIM3: (apb1rstr, tim3rst),
+    TIM6: (apb1rstr, tim6rst),
+    TIM21: (apb2rstr, tim21rst),
+    TIM22: (apb2rstr, tim22rst),
+
+    #[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
+    DAC: (apb1rstr, dacrst),
+    #[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
+    RNG: (ahbrstr, rngrst),
+    #[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
+    SPI2: (apb1rstr, spi2rst),
+
+    #[cfg(any(
+        feature = "io-STM32L021",
+        feature = "io-STM32L031",
+        feature = "io-STM32L051",
+        feature = "io-STM32L071",
+    ))]
+    I2C1: (apb1rstr, i2c1rst),
+    #[cfg(any(
+        feature = "io-STM32L021",
+        feature = "io-STM32L031",
+        feature = "io-STM32L051",
+        feature = "io-STM32L071",
+    ))]
+    LPUART1: (apb1rstr, lpuart1rst),
+    #[cfg(any(
+        feature = "io-STM32L021",
+        feature = "io-STM32L031",
+        feature = "io-STM32L051",
+        feature = "io-STM32L071",
+    ))]
+    USART2: (apb1rstr, usart2rst),
+    #[cfg(any(feature = "io-STM32L051", feature = "io-STM32L071"))]
+    I2C2: (apb1rstr, i2c2rst),
+    #[cfg(any(feature = "io-STM32L051", feature = "io-STM32L071"))]
+    USART1: (apb2rstr, usart1rst),
+    #[cfg(feature = "io-STM32L071")]
+    I2C3: (apb1rstr, i2c3rst),
+    #[cfg(feature = "io-STM32L071")]
+    USART4: (apb1rstr, usart4rst),
+    #[cfg(feature = "io-STM32L071")]
+    USART5: (apb1rstr, usart5rst),
+}
+
 /// RCC peripheral
 pub struct Rcc {
     pub clocks: Clocks,
@@ -204,6 +344,450 @@ impl Rcc {
         while self.rb.csr.read().lserdy().bit_is_clear() {}
         LSE(())
     }
+
+    /// Selects the kernel clock source of a peripheral
+    ///
+    /// This must be called before the peripheral is initialized, as the
+    /// peripheral APIs compute their baud rate or timing configuration from
+    /// the selected clock during initialization.
+    ///
+    /// HSI16 and LSI are enabled by this method, if necessary. LSE must have
+    /// been enabled before, using [`Rcc::enable_lse`].
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `src` is not available for `peripheral` (see
+    /// [`PeripheralClockSrc`]), or if `LSE` is selected while it is not
+    /// running.
+    pub fn set_clock_source(&mut self, peripheral: ClockedPeripheral, src: PeripheralClockSrc) {
+        use self::{ClockedPeripheral as P, PeripheralClockSrc as S};
+
+        match src {
+            S::HSI16 => {
+                self.rb.cr.modify(|_, w| w.hsi16on().set_bit());
+                while self.rb.cr.read().hsi16rdyf().bit_is_clear() {}
+            }
+            S::LSE => {
+                // LSE must be enabled using `enable_lse`
+                assert!(self.rb.csr.read().lserdy().bit_is_set());
+            }
+            S::LSI => {
+                self.rb.csr.modify(|_, w| w.lsion().set_bit());
+                while self.rb.csr.read().lsirdy().bit_is_clear() {}
+            }
+            S::APB | S::SYSCLK => {}
+        }
+
+        self.rb.ccipr.modify(|_, w| match (peripheral, src) {
+            (P::USART1, S::APB) => w.usart1sel().apb(),
+            (P::USART1, S::SYSCLK) => w.usart1sel().system(),
+            (P::USART1, S::HSI16) => w.usart1sel().hsi16(),
+            (P::USART1, S::LSE) => w.usart1sel().lse(),
+            (P::USART2, S::APB) => w.usart2sel().apb(),
+            (P::USART2, S::SYSCLK) => w.usart2sel().system(),
+            (P::USART2, S::HSI16) => w.usart2sel().hsi16(),
+            (P::USART2, S::LSE) => w.usart2sel().lse(),
+            (P::LPUART1, S::APB) => w.lpuart1sel().apb(),
+            (P::LPUART1, S::SYSCLK) => w.lpuart1sel().system(),
+            (P::LPUART1, S::HSI16) => w.lpuart1sel().hsi16(),
+            (P::LPUART1, S::LSE) => w.lpuart1sel().lse(),
+            (P::I2C1, S::APB) => w.i2c1sel().apb(),
+            (P::I2C1, S::SYSCLK) => w.i2c1sel().system(),
+            (P::I2C1, S::HSI16) => w.i2c1sel().hsi16(),
+            (P::I2C3, S::APB) => w.i2c3sel().apb(),
+            (P::I2C3, S::SYSCLK) => w.i2c3sel().system(),
+            (P::I2C3, S::HSI16) => w.i2c3sel().hsi16(),
+            (P::LPTIM1, S::APB) => w.lptim1sel().apb(),
+            (P::LPTIM1, S::LSI) => w.lptim1sel().lsi(),
+            (P::LPTIM1, S::HSI16) => w.lptim1sel().hsi16(),
+            (P::LPTIM1, S::LSE) => w.lptim1sel().lse(),
+            (peripheral, src) => panic!("{:?} can't be clocked from {:?}", peripheral, src),
+        });
+    }
+
+    /// Returns the currently selected kernel clock source of a peripheral
+    pub fn clock_source(&self, peripheral: ClockedPeripheral) -> PeripheralClockSrc {
+        let ccipr = self.rb.ccipr.read();
+        let bits = match peripheral {
+            ClockedPeripheral::USART1 => ccipr.usart1sel().bits(),
+            ClockedPeripheral::USART2 => ccipr.usart2sel().bits(),
+            ClockedPeripheral::LPUART1 => ccipr.lpuart1sel().bits(),
+            ClockedPeripheral::I2C1 => ccipr.i2c1sel().bits(),
+            ClockedPeripheral::I2C3 => ccipr.i2c3sel().bits(),
+            ClockedPeripheral::LPTIM1 => ccipr.lptim1sel().bits(),
+        };
+
+        match (peripheral, bits) {
+            (_, 0b00) => PeripheralClockSrc::APB,
+            (ClockedPeripheral::LPTIM1, 0b01) => PeripheralClockSrc::LSI,
+            (_, 0b01) => PeripheralClockSrc::SYSCLK,
+            (_, 0b10) => PeripheralClockSrc::HSI16,
+            // 0b11 is reserved for I2C1/I2C3, so this is always LSE
+            _ => PeripheralClockSrc::LSE,
+        }
+    }
+
+    /// Returns the kernel clock frequency of a peripheral
+    ///
+    /// This takes the clock source selected with [`Rcc::set_clock_source`]
+    /// into account.
+    pub fn peripheral_clk(&self, peripheral: ClockedPeripheral) -> Hertz {
+        match self.clock_source(peripheral) {
+            PeripheralClockSrc::APB => match peripheral {
+                ClockedPeripheral::USART1 => self.clocks.apb2_clk(),
+                _ => self.clocks.apb1_clk(),
+            },
+            PeripheralClockSrc::SYSCLK => self.clocks.sys_clk(),
+            PeripheralClockSrc::HSI16 => HSI_FREQ.hz(),
+            PeripheralClockSrc::LSE => LSE_FREQ.hz(),
+            PeripheralClockSrc::LSI => LSI_FREQ.hz(),
+        }
+    }
+
+    /// Resets a peripheral
+    ///
+    /// Pulses the reset bit of the peripheral in `RCC_APBxRSTR` or
+    /// `RCC_AHBRSTR`. All registers of the peripheral return to their reset
+    /// values, while its clock stays enabled. This can be used to recover a
+    /// peripheral that got stuck, before configuring it again.
+    ///
+    /// ```ignore
+    /// rcc.reset::<pac::I2C1>();
+    /// ```
+    pub fn reset<P: Reset>(&mut self) {
+        P::reset(self);
+    }
+
+    /// Returns the current HSI16 trimming value (`ICSCR.HSI16TRIM`)
+    pub fn hsi16_trim(&self) -> u8 {
+        self.rb.icscr.read().hsi16trim().bits()
+    }
+
+    /// Sets the HSI16 trimming value (`ICSCR.HSI16TRIM`)
+    ///
+    /// The trimming value is added to the factory calibration of HSI16. Valid
+    /// values are `0..=31`, with `16` being the reset value. Increasing the
+    /// value increases the frequency.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `trim` is larger than 31.
+    pub fn set_hsi16_trim(&mut self, trim: u8) {
+        assert!(trim < 32);
+        self.rb.icscr.modify(|_, w| w.hsi16trim().bits(trim));
+    }
+
+    /// Measures the HSI16 frequency against LSE
+    ///
+    /// TIM21 is clocked by LSE through its external trigger input, and counts
+    /// 256 LSE periods (about 7.8 ms). SysTick, which is clocked by the core
+    /// clock, measures the duration of those periods. As the core clock is
+    /// derived from HSI16, this gives the actual HSI16 frequency with a
+    /// resolution of about 8 ppm at 16 MHz, limited by the accuracy of the
+    /// LSE crystal.
+    ///
+    /// TIM21 is reset afterwards, and SysTick is left disabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the system clock isn't derived from HSI16.
+    pub fn measure_hsi16(&mut self, tim21: &mut TIM21, syst: &mut SYST, _: &LSE) -> Hertz {
+        const LSE_PERIODS: u32 = 256;
+
+        match self.clocks.source() {
+            ClockSrc::HSI16 | ClockSrc::PLL(PLLSource::HSI16, _, _) => {}
+            _ => panic!("System clock must be derived from HSI16"),
+        }
+
+        // Reset and enable TIM21
+        self.rb.apb2enr.modify(|_, w| w.tim21en().set_bit());
+        self.reset::<TIM21>();
+
+        // Count LSE periods using external clock mode 2
+        tim21.or.modify(|_, w| w.etr_rmp().lse());
+        tim21.smcr.modify(|_, w| w.ece().set_bit());
+        tim21.psc.write(|w| w.psc().bits(0));
+        // This is only unsafe for some variants, so we need this to suppress
+        // the warnings.
+        #[allow(unused_unsafe)]
+        tim21
+            .arr
+            .write(|w| unsafe { w.arr().bits((LSE_PERIODS - 1) as u16) });
+        tim21.cr1.modify(|_, w| w.urs().set_bit());
+        tim21.egr.write(|w| w.ug().set_bit());
+        tim21.sr.modify(|_, w| w.uif().clear_bit());
+
+        syst.set_clock_source(SystClkSource::Core);
+        syst.set_reload(0x00ff_ffff);
+        syst.clear_current();
+        syst.enable_counter();
+
+        tim21.cr1.modify(|_, w| w.cen().set_bit());
+
+        // Synchronize to an update event, then measure the next period
+        while tim21.sr.read().uif().bit_is_clear() {}
+        tim21.sr.modify(|_, w| w.uif().clear_bit());
+        let start = SYST::get_current();
+        while tim21.sr.read().uif().bit_is_clear() {}
+        let end = SYST::get_current();
+
+        syst.disable_counter();
+        self.reset::<TIM21>();
+        self.rb.apb2enr.modify(|_, w| w.tim21en().clear_bit());
+
+        // SysTick counts down
+        let cycles = u64::from(start.wrapping_sub(end) & 0x00ff_ffff);
+        let ahb_clk = cycles * u64::from(LSE_FREQ) / u64::from(LSE_PERIODS);
+
+        // Scale the measured AHB clock back to HSI16
+        Hertz((ahb_clk * u64::from(HSI_FREQ) / u64::from(self.clocks.ahb_clk().0)) as u32)
+    }
+
+    /// Trims HSI16 to be as close to 16 MHz as possible, using LSE as reference
+    ///
+    /// Starting from the current trimming value, this repeatedly measures
+    /// HSI16 using [`Rcc::measure_hsi16`] and steps the trimming value towards
+    /// 16 MHz, until the error stops decreasing. A trimming step changes the
+    /// frequency by roughly 0.5% (see the datasheet for exact values), so the
+    /// remaining error is about half a step at most, plus the LSE error. This
+    /// is usually sufficient for UART and, combined with CRS, for USB on
+    /// boards without a high-speed crystal.
+    ///
+    /// Returns the measured HSI16 frequency for the selected trimming value.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the system clock isn't derived from HSI16.
+    pub fn calibrate_hsi16(&mut self, tim21: &mut TIM21, syst: &mut SYST, lse: &LSE) -> Hertz {
+        let error = |freq: Hertz| (i64::from(freq.0) - i64::from(HSI_FREQ)).abs();
+
+        let mut trim = self.hsi16_trim();
+        let mut freq = self.measure_hsi16(tim21, syst, lse);
+
+        loop {
+            let next = if freq.0 < HSI_FREQ {
+                trim.checked_add(1).filter(|&t| t < 32)
+            } else {
+                trim.checked_sub(1)
+            };
+            let next = match next {
+                Some(next) => next,
+                None => break,
+            };
+
+            self.set_hsi16_trim(next);
+            let next_freq = self.measure_hsi16(tim21, syst, lse);
+
+            if error(next_freq) >= error(freq) {
+                self.set_hsi16_trim(trim);
+                break;
+            }
+
+            trim = next;
+            freq = next_freq;
+        }
+
+        freq
+    }
+
+    /// Measures the frequency of a clock signal using TIM21 input capture
+    ///
+    /// The signal is routed to TIM21 channel 1, which captures the counter on
+    /// every 8th rising edge. TIM21 is clocked from the APB2 timer clock, which
+    /// serves as the reference: The frequency is computed from the number of
+    /// timer ticks over 256 periods of the signal, so the result is only as
+    /// accurate as the system clock.
+    ///
+    /// The signal must be slower than about half the APB2 timer clock and
+    /// faster than about `apb2_tim_clk / 65536 * 8`. HSE is measured after
+    /// the RTC prescaler (`RTCPRE`), which usually brings it into range.
+    ///
+    /// Returns `None`, if no edges are detected, for example because the
+    /// selected oscillator isn't running.
+    ///
+    /// TIM21 is reset afterwards.
+    pub fn measure_clock(&mut self, tim21: &mut TIM21, clock: MeasuredClock) -> Option<Hertz> {
+        const CAPTURES: u32 = 32;
+        const IC_PRESCALER: u32 = 8;
+
+        // Reset and enable TIM21
+        self.rb.apb2enr.modify(|_, w| w.tim21en().set_bit());
+        self.reset::<TIM21>();
+
+        // Route the signal to TI1. The PAC only knows some of the values of
+        // `TI1_RMP`, see RM0377, section 20.4.14 for the full list.
+        let ti1_rmp = match clock {
+            MeasuredClock::Gpio => 0b000,
+            MeasuredClock::HseRtc => 0b010,
+            MeasuredClock::Msi => 0b011,
+            MeasuredClock::Lse => 0b100,
+            MeasuredClock::Lsi => 0b101,
+        };
+        tim21.or.modify(|_, w| unsafe { w.ti1_rmp().bits(ti1_rmp) });
+
+        // Capture on every 8th rising edge of TI1
+        tim21
+            .ccmr1_input()
+            .modify(|_, w| w.cc1s().bits(0b01).ic1psc().bits(0b11).ic1f().bits(0));
+        tim21
+            .ccer
+            .modify(|_, w| w.cc1p().clear_bit().cc1np().clear_bit().cc1e().set_bit());
+        tim21.psc.write(|w| w.psc().bits(0));
+        // This is only unsafe for some variants, so we need this to suppress
+        // the warnings.
+        #[allow(unused_unsafe)]
+        tim21.arr.write(|w| unsafe { w.arr().bits(0xffff) });
+        tim21.cr1.modify(|_, w| w.urs().set_bit());
+        tim21.egr.write(|w| w.ug().set_bit());
+        tim21.cr1.modify(|_, w| w.cen().set_bit());
+
+        // Waits for the next capture. Gives up, if the counter overflows twice
+        // without a capture.
+        let capture = |tim21: &mut TIM21| {
+            let mut overflows = 0;
+            tim21.sr.modify(|_, w| w.uif().clear_bit());
+            while tim21.sr.read().cc1if().bit_is_clear() {
+                if tim21.sr.read().uif().bit_is_set() {
+                    tim21.sr.modify(|_, w| w.uif().clear_bit());
+                    overflows += 1;
+                    if overflows >= 2 {
+                        return None;
+                    }
+                }
+            }
+            // Reading the capture register clears `CC1IF`
+            Some(tim21.ccr1.read().ccr().bits())
+        };
+
+        // The first capture only serves as the starting point
+        let mut ticks = Some(0u64);
+        let mut last = capture(tim21);
+        for _ in 0..CAPTURES {
+            match (last, capture(tim21)) {
+                (Some(previous), Some(current)) => {
+                    ticks = ticks.map(|t| t + u64::from(current.wrapping_sub(previous)));
+                    last = Some(current);
+                }
+                _ => {
+                    ticks = None;
+                    break;
+                }
+            }
+        }
+
+        self.reset::<TIM21>();
+        self.rb.apb2enr.modify(|_, w| w.tim21en().clear_bit());
+
+        let ticks = match ticks {
+            Some(ticks) if ticks > 0 => ticks,
+            _ => return None,
+        };
+
+        let divider = match clock {
+            MeasuredClock::HseRtc => match self.rb.cr.read().rtcpre().bits() {
+                0b00 => 2,
+                0b01 => 4,
+                0b10 => 8,
+                _ => 16,
+            },
+            _ => 1,
+        };
+
+        let periods = u64::from(CAPTURES * IC_PRESCALER);
+        let freq = periods * u64::from(self.clocks.apb2_tim_clk().0) / ticks;
+
+        Some(Hertz((freq * divider) as u32))
+    }
+
+    /// Enables the clock security system (CSS) for HSE (`CR.CSSHSEON`)
+    ///
+    /// If HSE fails while the CSS is enabled, HSE is switched off, the system
+    /// clock falls back to MSI (if it was derived from HSE) and an NMI is
+    /// generated. The CSS can't be disabled again, except by a reset.
+    ///
+    /// The NMI handler must call [`Rcc::handle_css_failure`], which clears
+    /// the interrupt flag, as the NMI would otherwise fire again right away:
+    ///
+    /// ``` ignore
+    /// use cortex_m_rt::exception;
+    /// use stm32l0xx_hal::rcc::Rcc;
+    ///
+    /// #[exception]
+    /// fn NMI() {
+    ///     if Rcc::handle_css_failure() {
+    ///         // Running from HSI16 now. Record the failure and reset, or
+    ///         // reconfigure the peripherals for the new clock frequency.
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics, if HSE is not running.
+    pub fn enable_css(&mut self) {
+        assert!(self.rb.cr.read().hserdy().bit_is_set());
+        self.rb.cr.modify(|_, w| w.csshseon().set_bit());
+    }
+
+    /// Handles a clock failure detected by the CSS
+    ///
+    /// This is meant to be called from the NMI handler, see
+    /// [`Rcc::enable_css`]. If a HSE failure has been detected, the CSS
+    /// interrupt flag is cleared (`CICR.CSSHSEC`), HSI16 is enabled and
+    /// selected as the system clock, and `true` is returned. Otherwise, this
+    /// does nothing and returns `false`.
+    ///
+    /// The AHB and APB prescalers are left unchanged, so all clocks run at
+    /// `16 MHz / HSE frequency` times their configured frequency afterwards.
+    /// [`Clocks`] isn't updated, so any peripheral whose timing depends on
+    /// its clock frequency must be reconfigured.
+    ///
+    /// As this can interrupt any other code accessing the RCC registers, a
+    /// read-modify-write of `CR` or `CFGR` interrupted by the NMI may undo
+    /// these changes. Keep clock configuration changes after enabling the CSS
+    /// to a minimum.
+    pub fn handle_css_failure() -> bool {
+        // Safe, as this is only called after a clock failure, when the clock
+        // configuration has to be changed anyway. See the note on concurrent
+        // accesses above.
+        let rcc = unsafe { &*RCC::ptr() };
+
+        if rcc.cifr.read().csshsef().bit_is_clear() {
+            return false;
+        }
+        rcc.cicr.write(|w| w.csshsec().set_bit());
+
+        rcc.cr.modify(|_, w| w.hsi16on().set_bit());
+        while rcc.cr.read().hsi16rdyf().bit_is_clear() {}
+        rcc.cfgr.modify(|_, w| unsafe { w.sw().bits(0b01) });
+        while rcc.cfgr.read().sws().bits() != 0b01 {}
+
+        true
+    }
+}
+
+/// Returns whether anything besides the ADC depends on HSI16
+///
+/// HSI16 is in use, if it is the system clock, the PLL input while the PLL
+/// is running, the MCO source, or the kernel clock of a peripheral (`CCIPR`).
+pub(crate) fn hsi16_in_use() -> bool {
+    // Safe, as we're only doing atomic reads.
+    let rcc = unsafe { &*RCC::ptr() };
+
+    // Raw bits, as not every field is available on every device. The kernel
+    // clock selections are USART1SEL, USART2SEL, LPUART1SEL, I2C1SEL,
+    // I2C3SEL and LPTIM1SEL, where 0b10 selects HSI16 for all of them.
+    let ccipr = rcc.ccipr.read().bits();
+    let kernel_clock = [0, 2, 10, 12, 16, 18]
+        .iter()
+        .any(|&offset| (ccipr >> offset) & 0b11 == 0b10);
+
+    let cfgr = rcc.cfgr.read().bits();
+    let sysclk = (cfgr >> 2) & 0b11 == 0b01;
+    let pll = rcc.cr.read().pllon().bit_is_set() && cfgr & (1 << 16) == 0;
+    let mco = (cfgr >> 24) & 0b1111 == 0b0010;
+
+    kernel_clock || sysclk || pll || mco
 }
 
 #[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
@@ -1,3 +1,4 @@
+use crate::pac::RCC;
 use crate::rcc::{Rcc, HSI48};
 
 pub use crate::pac::{rng, RNG};
@@ -10,8 +11,7 @@ impl Rng {
     // Initializes the peripheral
     pub fn new(rng: RNG, rcc: &mut Rcc, _: HSI48) -> Rng {
         // Reset peripheral
-        rcc.rb.ahbrstr.modify(|_, w| w.rngrst().set_bit());
-        rcc.rb.ahbrstr.modify(|_, w| w.rngrst().clear_bit());
+        rcc.reset::<RNG>();
 
         // Enable peripheral clock
         rcc.rb.ahbenr.modify(|_, w| w.rngen().set_bit());
@@ -42,4 +42,73 @@ impl Rng {
     pub fn take_result(&mut self) -> u32 {
         self.rng.dr.read().bits()
     }
+
+    /// Powers up the RNG for a batch of at most `batch` random words
+    ///
+    /// Enables the peripheral clock and the analog block, passes an iterator
+    /// over up to `batch` random words to `f`, then disables the RNG and
+    /// gates its clock again. This saves current in low-power designs that
+    /// only need random numbers occasionally.
+    ///
+    /// The RNG is left powered down after this returns. Call `power_up` before
+    /// using `wait`/`take_result` directly again.
+    pub fn with_power<F, R>(&mut self, batch: usize, f: F) -> R
+    where
+        F: FnOnce(&mut Batch) -> R,
+    {
+        self.power_up();
+
+        let result = f(&mut Batch {
+            rng: self,
+            remaining: batch,
+        });
+
+        self.power_down();
+
+        result
+    }
+
+    /// Enables the peripheral clock and the RNG
+    pub fn power_up(&mut self) {
+        // Safe, as we only modify the RNG clock enable bit, and do so within
+        // a critical section.
+        let rcc = unsafe { &*RCC::ptr() };
+        cortex_m::interrupt::free(|_| rcc.ahbenr.modify(|_, w| w.rngen().set_bit()));
+
+        self.enable();
+    }
+
+    /// Disables the RNG and gates its peripheral clock
+    pub fn power_down(&mut self) {
+        self.disable();
+
+        // Safe, as we only modify the RNG clock enable bit, and do so within
+        // a critical section.
+        let rcc = unsafe { &*RCC::ptr() };
+        cortex_m::interrupt::free(|_| rcc.ahbenr.modify(|_, w| w.rngen().clear_bit()));
+    }
+}
+
+/// A batch of random words, created by [`Rng::with_power`]
+pub struct Batch<'r> {
+    rng: &'r mut Rng,
+    remaining: usize,
+}
+
+impl Iterator for Batch<'_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        self.rng.wait();
+        Some(self.rng.take_result())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
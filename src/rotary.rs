@@ -0,0 +1,116 @@
+//! Rotary encoder input using two GPIO pins
+//!
+//! [`RotaryEncoder`] decodes the quadrature signal of a mechanical rotary
+//! encoder (the common panel knob) in software, so it works on any two input
+//! pins. Use it if no timer with an encoder mode is available. Otherwise,
+//! prefer the hardware decoder in the [`encoder`](../encoder/index.html)
+//! module.
+//!
+//! The decoder only needs to see every change of the pin levels. Listen on
+//! both pins' EXTI lines with `TriggerEdge::Both` and call
+//! [`RotaryEncoder::poll`] from the interrupt handler, or call it frequently
+//! from the main loop.
+
+use crate::hal::digital::v2::InputPin;
+
+/// Direction of each transition, indexed by `previous << 2 | current`
+///
+/// Invalid transitions (both pins changed, so a state was missed) count as
+/// no movement.
+const TRANSITIONS: [i8; 16] = [0, -1, 1, 0, 1, 0, 0, -1, -1, 0, 0, 1, 0, 1, -1, 0];
+
+/// Number of quadrature states between two detents
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Detents {
+    /// One detent per full quadrature cycle (4 transitions)
+    ///
+    /// At rest, both pins are high.
+    FullStep,
+    /// One detent per half quadrature cycle (2 transitions)
+    ///
+    /// At rest, both pins are either high or low.
+    HalfStep,
+}
+
+/// A rotary encoder, connected to two input pins
+pub struct RotaryEncoder<A, B> {
+    pin_a: A,
+    pin_b: B,
+    detents: Detents,
+    state: u8,
+    steps: i8,
+}
+
+impl<A, B, E> RotaryEncoder<A, B>
+where
+    A: InputPin<Error = E>,
+    B: InputPin<Error = E>,
+{
+    /// Creates a new `RotaryEncoder`
+    ///
+    /// The pins should be configured as inputs with pull-ups, if the encoder
+    /// connects them to ground.
+    pub fn new(pin_a: A, pin_b: B, detents: Detents) -> Result<Self, E> {
+        let mut encoder = RotaryEncoder {
+            pin_a,
+            pin_b,
+            detents,
+            state: 0,
+            steps: 0,
+        };
+        encoder.state = encoder.read_state()?;
+        Ok(encoder)
+    }
+
+    /// Samples the pins and returns the movement since the last detent
+    ///
+    /// Returns `1` when a detent is reached in clockwise direction (pin A
+    /// leading), `-1` in counter-clockwise direction and `0` otherwise. A
+    /// contact bouncing back and forth is ignored, as it doesn't reach a
+    /// detent.
+    pub fn poll(&mut self) -> Result<i8, E> {
+        let state = self.read_state()?;
+        if state == self.state {
+            return Ok(0);
+        }
+
+        let index = usize::from(self.state << 2 | state);
+        self.steps += TRANSITIONS[index];
+        self.state = state;
+
+        let at_detent = match self.detents {
+            Detents::FullStep => state == 0b11,
+            Detents::HalfStep => state == 0b11 || state == 0b00,
+        };
+        if !at_detent {
+            return Ok(0);
+        }
+
+        // Accept a detent, even if one of the transitions has been missed
+        let threshold = match self.detents {
+            Detents::FullStep => 2,
+            Detents::HalfStep => 1,
+        };
+        let movement = if self.steps >= threshold {
+            1
+        } else if self.steps <= -threshold {
+            -1
+        } else {
+            0
+        };
+        self.steps = 0;
+
+        Ok(movement)
+    }
+
+    /// Returns the pins
+    pub fn release(self) -> (A, B) {
+        (self.pin_a, self.pin_b)
+    }
+
+    fn read_state(&self) -> Result<u8, E> {
+        let a = self.pin_a.is_high()? as u8;
+        let b = self.pin_b.is_high()? as u8;
+        Ok(a << 1 | b)
+    }
+}
@@ -2,12 +2,14 @@
 //!
 //! See STM32L0x2 reference manual, chapter 26.
 
+use cortex_m::peripheral::SCB;
 use void::Void;
 
 use crate::{
-    hal::timer::{self, Cancel as _},
+    exti::{ConfigurableLine, Exti, TriggerEdge},
+    hal::timer::{self, Cancel as _, CountDown as _},
     pac,
-    pwr::PWR,
+    pwr::{StopModeConfig, PWR},
     rcc::Rcc,
     time::U32Ext,
 };
@@ -257,6 +259,155 @@ impl RTC {
         })
     }
 
+    /// Sets up Alarm A to fire once, at the given date/time
+    ///
+    /// The hardware alarm can only match the day of the month, hour, minute
+    /// and second of `at`, so without further action, it would repeat every
+    /// month. Call [`RTC::handle_oneshot_alarm`] from the RTC interrupt handler
+    /// (or poll it) to clear the alarm flag and disable the alarm once it has
+    /// fired.
+    ///
+    /// This enables the Alarm A interrupt. To wake up from Stop or Standby
+    /// mode, EXTI line 17 must be configured as well.
+    pub fn set_oneshot_alarm(&mut self, at: Instant) {
+        self.write(|rtc| {
+            // Disable Alarm A, so it can be configured
+            rtc.cr
+                .modify(|_, w| w.alrae().clear_bit().alraie().clear_bit());
+
+            // Wait until we're allowed to update the alarm configuration
+            while rtc.isr.read().alrawf().bit_is_clear() {}
+
+            rtc.alrmar.write(|w|
+                // Safe, as `Instant` verifies that its fields are valid.
+                w
+                    // Match day of the month
+                    .msk4().clear_bit()
+                    .wdsel().clear_bit()
+                    .dt().bits(at.day / 10)
+                    .du().bits(at.day % 10)
+                    // Match hour (24-hour format)
+                    .msk3().clear_bit()
+                    .pm().clear_bit()
+                    .ht().bits(at.hour / 10)
+                    .hu().bits(at.hour % 10)
+                    // Match minute
+                    .msk2().clear_bit()
+                    .mnt().bits(at.minute / 10)
+                    .mnu().bits(at.minute % 10)
+                    // Match second
+                    .msk1().clear_bit()
+                    .st().bits(at.second / 10)
+                    .su().bits(at.second % 10));
+
+            // Clear a stale alarm flag, then enable the alarm
+            rtc.isr.modify(|_, w| w.alraf().clear_bit());
+            rtc.cr.modify(|_, w| w.alrae().set_bit().alraie().set_bit());
+        })
+    }
+
+    /// Disables Alarm A, if it has fired
+    ///
+    /// Returns `true`, if Alarm A has fired. In that case, the alarm flag is
+    /// cleared and the alarm and its interrupt are disabled, so it won't fire
+    /// again. Returns `false` otherwise.
+    ///
+    /// `RTC_CR` and `RTC_ISR` are write-protected. This method unlocks them by
+    /// writing `0xCA` and `0x53` to `RTC_WPR`, and locks them again afterwards
+    /// by writing `0xFF`, so it can be called directly from the interrupt
+    /// handler. Please note that the EXTI line 17 pending bit must be cleared
+    /// separately.
+    pub fn handle_oneshot_alarm(&mut self) -> bool {
+        if self.rtc.isr.read().alraf().bit_is_clear() {
+            return false;
+        }
+
+        self.write(|rtc| {
+            rtc.cr
+                .modify(|_, w| w.alrae().clear_bit().alraie().clear_bit());
+            rtc.isr.modify(|_, w| w.alraf().clear_bit());
+        });
+
+        true
+    }
+
+    /// Runs `f` every `interval` seconds, staying in Stop mode in between
+    ///
+    /// The wakeup timer is started with the given interval, and its interrupt
+    /// and EXTI line (line 20) are enabled. The microcontroller then enters
+    /// Stop mode, wakes up when the wakeup timer fires, calls `f` and goes
+    /// back to Stop mode. This repeats until `f` returns `false`, which stops
+    /// the wakeup timer and disables its interrupt again.
+    ///
+    /// The RTC interrupt stays masked in the NVIC, so no interrupt handler is
+    /// needed. Other interrupts that are unmasked and can wake up the
+    /// microcontroller from Stop mode also end Stop mode early. `f` is only
+    /// called for wakeups caused by the wakeup timer.
+    ///
+    /// Waking up from Stop mode restores the clock source as described for
+    /// [`PWR::stop_mode`], so the same restrictions apply.
+    ///
+    /// # Average current
+    ///
+    /// In Stop mode with the RTC running from LSE, the microcontroller draws
+    /// about 1 µA (see the datasheet for the exact figure of your part and
+    /// temperature). The average current is roughly
+    /// `I_stop + I_run * t_active / interval`, where `t_active` is the time
+    /// spent in `f` plus a few µs for waking up. For example, running at
+    /// 16 MHz (about 2 mA) for 1 ms every 10 s adds 0.2 µA to `I_stop`.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `interval` is not in the range `1 <= interval < 2^17`.
+    pub fn every<F>(
+        &mut self,
+        interval: u32,
+        exti: &mut Exti,
+        pwr: &mut PWR,
+        scb: &mut SCB,
+        rcc: &mut Rcc,
+        mut f: F,
+    ) where
+        F: FnMut() -> bool,
+    {
+        let exti_line = ConfigurableLine::RtcWakeup;
+        let interrupts = || Interrupts {
+            wakeup_timer: true,
+            ..Interrupts::default()
+        };
+
+        self.enable_interrupts(interrupts());
+        exti.listen_configurable(exti_line, TriggerEdge::Rising);
+        self.wakeup_timer().start(interval);
+
+        loop {
+            exti.wait_for_irq(
+                exti_line,
+                pwr.stop_mode(
+                    scb,
+                    rcc,
+                    StopModeConfig {
+                        ultra_low_power: true,
+                    },
+                ),
+            );
+
+            if self.wakeup_timer().wait().is_err() {
+                // Woken up by something else
+                continue;
+            }
+
+            if !f() {
+                break;
+            }
+        }
+
+        // Can't fail, as the error type is `Void`.
+        self.wakeup_timer().cancel().unwrap();
+        self.disable_interrupts(interrupts());
+        exti.unlisten(exti_line);
+    }
+
     /// Access the wakeup timer
     pub fn wakeup_timer(&mut self) -> WakeupTimer {
         WakeupTimer { rtc: self }
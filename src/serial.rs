@@ -3,12 +3,14 @@ use core::marker::PhantomData;
 use core::ptr;
 
 use nb::block;
+use void::Void;
 
 use crate::gpio::{AltMode, PinMode};
 use crate::hal;
 use crate::hal::prelude::*;
+use crate::hal::spi::{Mode, Phase, Polarity};
 pub use crate::pac::{LPUART1, USART1, USART2, USART4, USART5};
-use crate::rcc::{Rcc, LSE};
+use crate::rcc::{ClockedPeripheral, PeripheralClockSrc, Rcc, LSE};
 
 #[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
 use core::{
@@ -47,6 +49,8 @@ pub enum Error {
     Overrun,
     /// Parity check error
     Parity,
+    /// The buffer was too small to hold the received data
+    BufferFull,
 }
 
 /// Interrupt event
@@ -92,11 +96,20 @@ pub enum StopBits {
     STOP1P5,
 }
 
+/// Serial configuration
+///
+/// The STM32L0 USARTs have no clock prescaler, so the baudrate must be
+/// reachable from the kernel clock of the USART. The constructors return
+/// `InvalidConfig`, if it isn't. For example, LPUART1 can't run at 300 baud
+/// from a 16 MHz clock; select LSE as its kernel clock for low baudrates.
 pub struct Config {
     pub baudrate: Bps,
     pub wordlength: WordLength,
     pub parity: Parity,
     pub stopbits: StopBits,
+    pub data_inversion: bool,
+    pub msb_first: bool,
+    pub overrun_disabled: bool,
 }
 
 impl Config {
@@ -130,15 +143,249 @@ impl Config {
         self
     }
 
+    /// Sets the number of stop bits (`CR2.STOP`)
+    ///
+    /// LPUART1 only supports 1 and 2 stop bits. Passing 0.5 or 1.5 stop bits
+    /// for LPUART1 makes the constructor return `InvalidConfig`.
     pub fn stopbits(mut self, stopbits: StopBits) -> Self {
         self.stopbits = stopbits;
         self
     }
+
+    /// Inverts the data bits (`CR2.DATAINV`)
+    ///
+    /// Data is transmitted and received with a high level meaning 0. The
+    /// parity bit is inverted as well, but start and stop bits are not. Use
+    /// this for links that invert the data, but keep the idle level high.
+    ///
+    /// The USARTs and LPUART1 support data inversion with every word length,
+    /// parity, stop bit and bit order setting, so this needs no validation.
+    pub fn data_inversion(mut self, inverted: bool) -> Self {
+        self.data_inversion = inverted;
+        self
+    }
+
+    /// Transmits and receives the most significant bit first (`CR2.MSBFIRST`)
+    ///
+    /// The data bits are reversed, excluding the parity bit, which is still
+    /// sent last. Like [`Config::data_inversion`], this is supported with
+    /// every other setting, by the USARTs and LPUART1.
+    pub fn msb_first(mut self, msb_first: bool) -> Self {
+        self.msb_first = msb_first;
+        self
+    }
+
+    /// Disables overrun detection (`CR3.OVRDIS`)
+    ///
+    /// By default, a byte that is received before the previous one has been
+    /// read is discarded, and reading fails with `Error::Overrun` until the
+    /// error has been handled. With overrun detection disabled, the new byte
+    /// overwrites the previous one instead, and reading always returns the
+    /// most recently received byte.
+    ///
+    /// Use this for links where only the latest value matters and losing
+    /// bytes is acceptable, like streaming telemetry. Lost bytes are not
+    /// reported at all, so don't use this if the data has to be complete.
+    pub fn disable_overrun(mut self) -> Self {
+        self.overrun_disabled = true;
+        self
+    }
+}
+
+impl Config {
+    /// Returns the DMX512 configuration: 250 kbaud, 8 data bits, no parity and
+    /// 2 stop bits
+    pub fn dmx512() -> Self {
+        Config::default()
+            .baudrate(250_000.bps())
+            .stopbits(StopBits::STOP2)
+    }
 }
 
 #[derive(Debug)]
 pub struct InvalidConfig;
 
+/// Clock configuration for synchronous mode
+///
+/// Describes the clock that the USART outputs on its CK pin in synchronous
+/// mode, see `Serial::enable_synchronous`. The clock polarity and phase have
+/// the same meaning as for SPI (`CR2.CPOL` and `CR2.CPHA`).
+///
+/// The USART doesn't output a clock pulse for the last data bit by default.
+/// Most shift registers only latch a bit on a clock edge, so the last bit
+/// would never be shifted in, and everything would be off by one bit. This
+/// is why the last bit clock pulse (`CR2.LBCL`) is enabled by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SyncClockConfig {
+    pub polarity: Polarity,
+    pub phase: Phase,
+    pub last_bit_clock: bool,
+}
+
+impl SyncClockConfig {
+    /// Creates a clock configuration with the polarity and phase of `mode`
+    pub fn new(mode: Mode) -> Self {
+        SyncClockConfig {
+            polarity: mode.polarity,
+            phase: mode.phase,
+            last_bit_clock: true,
+        }
+    }
+
+    /// Outputs a clock pulse for the last data bit (`CR2.LBCL`)
+    pub fn last_bit_clock(mut self, enabled: bool) -> Self {
+        self.last_bit_clock = enabled;
+        self
+    }
+}
+
+impl Default for SyncClockConfig {
+    fn default() -> Self {
+        SyncClockConfig::new(hal::spi::MODE_0)
+    }
+}
+
+/// Configuration for reading lines, see `Serial::read_line_with`
+///
+/// By default, lines end with `b'\n'`, are stored as received and aren't
+/// echoed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineConfig {
+    pub terminator: u8,
+    pub strip_cr: bool,
+    pub echo: bool,
+}
+
+impl LineConfig {
+    /// Sets the byte that ends a line
+    pub fn terminator(mut self, terminator: u8) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Removes a `b'\r'` right before the terminator
+    ///
+    /// Modems and ESP-AT modules end lines with `"\r\n"`. With this, the
+    /// lines can be compared without the line ending. The `b'\r'` needs room
+    /// in the buffer, until it is removed.
+    pub fn strip_cr(mut self, enabled: bool) -> Self {
+        self.strip_cr = enabled;
+        self
+    }
+
+    /// Writes every received byte back, including the terminator
+    ///
+    /// This lets a user on a terminal see what they're typing.
+    pub fn echo(mut self, enabled: bool) -> Self {
+        self.echo = enabled;
+        self
+    }
+}
+
+impl Default for LineConfig {
+    fn default() -> Self {
+        LineConfig {
+            terminator: b'\n',
+            strip_cr: false,
+            echo: false,
+        }
+    }
+}
+
+/// Maximum length of a DMX512 frame: the start code and 512 channel bytes
+pub const DMX_FRAME_LEN: usize = 513;
+
+/// DMX512 receiver
+///
+/// Created by `Serial::into_dmx_receiver`. Frames are delimited by breaks: A
+/// break (the line held low for longer than a character, at least 88 µs in
+/// DMX512) is received as a framing error with a data value of zero. The
+/// mark-after-break that follows is just an idle line to the receiver, so it
+/// doesn't need special handling. The bytes received after the break, up to
+/// the next break or until [`DMX_FRAME_LEN`] bytes have been received, form
+/// the frame. The first byte is the start code, followed by up to 512
+/// channels.
+pub struct DmxReceiver<USART> {
+    serial: Serial<USART>,
+    buffer: [u8; DMX_FRAME_LEN],
+    len: usize,
+    in_frame: bool,
+}
+
+/// Interrupt-driven receiver with a software ring buffer
+///
+/// Created by `Rx::into_buffered`. Call [`on_interrupt`] from the USART
+/// interrupt handler to move received bytes into the buffer, and [`read`] to
+/// take them out again.
+///
+/// When the number of buffered bytes reaches the watermark, a flag is set
+/// that stays set until enough bytes have been read to bring the level below
+/// the watermark again. This gives the application a chance to throttle the
+/// sender (for example using XON/XOFF or a GPIO handshake line) before the
+/// buffer overflows.
+///
+/// [`on_interrupt`]: #method.on_interrupt
+/// [`read`]: #method.read
+pub struct BufferedRx<USART> {
+    rx: Rx<USART>,
+    buffer: &'static mut [u8],
+    start: usize,
+    len: usize,
+    watermark: usize,
+    above_watermark: bool,
+}
+
+impl<USART> BufferedRx<USART> {
+    /// Takes the oldest byte out of the buffer
+    pub fn read(&mut self) -> nb::Result<u8, Void> {
+        if self.len == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let byte = self.buffer[self.start];
+        self.start = (self.start + 1) % self.buffer.len();
+        self.len -= 1;
+
+        if self.len < self.watermark {
+            self.above_watermark = false;
+        }
+
+        Ok(byte)
+    }
+
+    /// Returns the number of buffered bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true`, if no bytes are buffered
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the watermark level in bytes
+    pub fn watermark(&self) -> usize {
+        self.watermark
+    }
+
+    /// Returns `true`, if the buffer is filled up to the watermark
+    pub fn is_above_watermark(&self) -> bool {
+        self.above_watermark
+    }
+
+    fn push(&mut self, byte: u8) -> Result<(), Error> {
+        if self.len == self.buffer.len() {
+            return Err(Error::Overrun);
+        }
+
+        let end = (self.start + self.len) % self.buffer.len();
+        self.buffer[end] = byte;
+        self.len += 1;
+
+        Ok(())
+    }
+}
+
 impl Default for Config {
     fn default() -> Config {
         let baudrate = 9_600_u32.bps();
@@ -147,6 +394,9 @@ impl Default for Config {
             wordlength: WordLength::DataBits8,
             parity: Parity::ParityNone,
             stopbits: StopBits::STOP1,
+            data_inversion: false,
+            msb_first: false,
+            overrun_disabled: false,
         }
     }
 }
@@ -161,6 +411,11 @@ pub trait RxPin<USART> {
     fn setup(&self);
 }
 
+/// Trait to mark serial pins with clock output capability.
+pub trait CkPin<USART> {
+    fn setup(&self);
+}
+
 /// Macro to implement `TxPin` / `RxPin` for a certain pin, using a certain
 /// alternative function and for a certain serial peripheral.
 macro_rules! impl_pins {
@@ -177,6 +432,7 @@ macro_rules! impl_pins {
 
 #[cfg(feature = "io-STM32L021")]
 impl_pins!(
+    PA4, AF4, USART2, CkPin;
     PA0, AF0, USART2, RxPin;
     PA0, AF6, LPUART1, RxPin;
     PA1, AF6, LPUART1, TxPin;
@@ -200,6 +456,7 @@ impl_pins!(
 
 #[cfg(feature = "io-STM32L031")]
 impl_pins!(
+    PA4, AF4, USART2, CkPin;
     PA2, AF4, USART2, TxPin;
     PA2, AF6, LPUART1, TxPin;
     PA3, AF4, USART2, RxPin;
@@ -219,6 +476,8 @@ impl_pins!(
 
 #[cfg(feature = "io-STM32L051")]
 impl_pins!(
+    PA4, AF4, USART2, CkPin;
+    PA8, AF4, USART1, CkPin;
     PA2, AF4, USART2, TxPin;
     PA3, AF4, USART2, RxPin;
     PA9, AF4, USART1, TxPin;
@@ -237,6 +496,8 @@ impl_pins!(
 
 #[cfg(feature = "io-STM32L071")]
 impl_pins!(
+    PA4, AF4, USART2, CkPin;
+    PA8, AF4, USART1, CkPin;
     PA0, AF6, USART4, TxPin;
     PA1, AF6, USART4, RxPin;
     PA2, AF4, USART2, TxPin;
@@ -294,9 +555,24 @@ pub struct Tx<USART> {
     _usart: PhantomData<USART>,
 }
 
+/// Single-wire half-duplex serial interface
+///
+/// Created by `HalfDuplex::new`. The USART transmits and receives on the TX
+/// pin (`CR3.HDSEL`). The pin is only driven while a byte is transmitted and
+/// released otherwise, so other devices can drive the line in between.
+///
+/// The receiver is disabled while transmitting, so transmitted bytes are not
+/// echoed back. It is enabled again by `flush`, once the last byte has been
+/// shifted out. Make sure to call `flush` before waiting for a response.
+pub struct HalfDuplex<USART> {
+    usart: USART,
+    rx: Rx<USART>,
+    tx: Tx<USART>,
+}
+
 macro_rules! usart {
     ($(
-        $USARTX:ident: ($usartX:ident, $apbXenr:ident, $usartXen:ident, $pclkX:ident, $SerialExt:ident),
+        $USARTX:ident: ($usartX:ident, $apbXenr:ident, $usartXen:ident, $clk_sel:expr, $SerialExt:ident),
     )+) => {
         $(
             pub trait $SerialExt<TX, RX> {
@@ -328,25 +604,91 @@ macro_rules! usart {
                     tx.setup();
                     rx.setup();
 
+                    Self::configure(&usart, &config, rcc, true, true)?;
+
+                    Ok(Serial {
+                        usart,
+                        tx: Tx { _usart: PhantomData },
+                        rx: Rx { _usart: PhantomData },
+                    })
+                }
+
+                /// Configures and enables the USART
+                ///
+                /// Only the directions selected by `tx` and `rx` are enabled
+                /// (`CR1.TE` and `CR1.RE`).
+                fn configure(
+                    usart: &$USARTX,
+                    config: &Config,
+                    rcc: &mut Rcc,
+                    tx: bool,
+                    rx: bool,
+                ) -> Result<(), InvalidConfig> {
+                    // LPUART1 only supports 1 and 2 stop bits
+                    if stringify!($usartX) == "lpuart1" {
+                        match config.stopbits {
+                            StopBits::STOP0P5 | StopBits::STOP1P5 => {
+                                return Err(InvalidConfig)
+                            }
+                            _ => (),
+                        }
+                    }
+
                     // Enable clock for USART
                     rcc.rb.$apbXenr.modify(|_, w| w.$usartXen().set_bit());
 
-                    // Calculate correct baudrate divisor on the fly
-                    let div = (rcc.clocks.$pclkX().0 * 25) / (4 * config.baudrate.0);
-                    let mantissa = div / 100;
-                    let fraction = ((div - mantissa * 100) * 16 + 50) / 100;
-                    let mut brr = mantissa << 4 | fraction;
+                    // Use the kernel clock selected in `RCC_CCIPR`, if this
+                    // USART has one. Otherwise it is clocked from APB1.
+                    let clk: Option<ClockedPeripheral> = $clk_sel;
+                    let clk = match clk {
+                        Some(peripheral) => rcc.peripheral_clk(peripheral).0,
+                        None => rcc.clocks.apb1_clk().0,
+                    };
 
-                    if stringify!($usartX) == "lpuart1" {
-                        brr *= 256
+                    // Calculate correct baudrate divisor on the fly
+                    let brr = if stringify!($usartX) == "lpuart1" {
+                        // LPUART1 requires 256 * f_ck / baudrate. Use 64 bits,
+                        // as that overflows 32 bits for clocks above 16 MHz.
+                        (u64::from(clk) * 256 / u64::from(config.baudrate.0)) as u32
+                    } else {
+                        let div = (clk * 25) / (4 * config.baudrate.0);
+                        let mantissa = div / 100;
+                        let fraction = ((div - mantissa * 100) * 16 + 50) / 100;
+                        mantissa << 4 | fraction
+                    };
+
+                    // The L0 USARTs have no clock prescaler (`PRESC`), so the
+                    // baudrate has to be reachable with the kernel clock
+                    // directly. LPUART1 requires `BRR >= 0x300` and only has
+                    // 20 bits, the other USARTs have 16 bits and require
+                    // `BRR >= 16` with 16x oversampling.
+                    let brr_range = if stringify!($usartX) == "lpuart1" {
+                        0x300..=0xf_ffff
+                    } else {
+                        0x10..=0xffff
+                    };
+                    if !brr_range.contains(&brr) {
+                        return Err(InvalidConfig);
                     }
 
                     usart
                         .brr
                         .write(|w| unsafe { w.bits(brr) });
 
-                    // Reset other registers to disable advanced USART features
-                    usart.cr2.reset();
+                    // Configure stop bits. This has to happen before the
+                    // USART is enabled, as `CR2` is read-only while `UE` is
+                    // set. Writing the whole register also disables advanced
+                    // USART features.
+                    usart.cr2.write(|w|
+                        w.stop().bits(match config.stopbits {
+                            StopBits::STOP1 => 0b00,
+                            StopBits::STOP0P5 => 0b01,
+                            StopBits::STOP2 => 0b10,
+                            StopBits::STOP1P5 => 0b11,
+                        })
+                        .datainv().bit(config.data_inversion)
+                        .msbfirst().bit(config.msb_first)
+                    );
 
                     // Enable DMA
                     usart.cr3.write(|w|
@@ -356,17 +698,19 @@ macro_rules! usart {
                             // Enable DMA
                             .dmat().enabled()
                             .dmar().enabled()
+                            // Overwrite unread data, if requested
+                            .ovrdis().bit(config.overrun_disabled)
                     );
 
-                    // Enable transmission and receiving
+                    // Enable transmission and/or receiving
                     // and configure frame
                     usart.cr1.write(|w| {
                         w.ue()
                             .set_bit()
                             .te()
-                            .set_bit()
+                            .bit(tx)
                             .re()
-                            .set_bit()
+                            .bit(rx)
                             .m0()
                             .bit(match config.wordlength {
                                 WordLength::DataBits8 => false,
@@ -382,19 +726,134 @@ macro_rules! usart {
                             })
                     });
 
-                    usart.cr2.write(|w|
-                        w.stop().bits(match config.stopbits {
-                            StopBits::STOP1 => 0b00,
-                            StopBits::STOP0P5 => 0b01,
-                            StopBits::STOP2 => 0b10,
-                            StopBits::STOP1P5 => 0b11,
-                        })
-                    );
-                    Ok(Serial {
-                        usart,
-                        tx: Tx { _usart: PhantomData },
-                        rx: Rx { _usart: PhantomData },
-                    })
+                    Ok(())
+                }
+
+                /// Enables synchronous mode, using `ck` as the clock output
+                ///
+                /// The USART acts as a synchronous master (`CR2.CLKEN`). It
+                /// outputs one clock pulse for each transmitted data bit on
+                /// `ck`, as configured by `clock`, and samples the received
+                /// data with the same clock. Start and stop bits are not
+                /// clocked. Combined with `Config::msb_first`, this can be used
+                /// to talk to shift registers and other SPI-like devices.
+                ///
+                /// Returns `InvalidConfig` for LPUART1, which doesn't support
+                /// synchronous mode.
+                pub fn enable_synchronous<CK>(
+                    &mut self,
+                    ck: CK,
+                    clock: SyncClockConfig,
+                ) -> Result<(), InvalidConfig>
+                where
+                    CK: CkPin<$USARTX>,
+                {
+                    if stringify!($usartX) == "lpuart1" {
+                        return Err(InvalidConfig);
+                    }
+
+                    ck.setup();
+
+                    let mut bits = 1 << 11; // CLKEN
+                    if clock.polarity == Polarity::IdleHigh {
+                        bits |= 1 << 10; // CPOL
+                    }
+                    if clock.phase == Phase::CaptureOnSecondTransition {
+                        bits |= 1 << 9; // CPHA
+                    }
+                    if clock.last_bit_clock {
+                        bits |= 1 << 8; // LBCL
+                    }
+
+                    // `CR2` can only be written while the USART is disabled.
+                    // These bits are written directly, as LPUART1 doesn't
+                    // have them, so they're missing from its register API.
+                    self.usart.cr1.modify(|_, w| w.ue().clear_bit());
+                    self.usart.cr2.modify(|r, w| unsafe {
+                        w.bits(r.bits() & !0xf00 | bits)
+                    });
+                    self.usart.cr1.modify(|_, w| w.ue().set_bit());
+
+                    Ok(())
+                }
+
+                /// Enables mute mode, waking up on a matching address mark
+                ///
+                /// Used by the nodes on a multidrop bus (e.g. RS-485) to only
+                /// receive the data that is addressed to them. Mute mode
+                /// (`CR1.MME`) is enabled with address mark detection as the
+                /// wakeup method (`CR1.WAKE`), comparing 7-bit addresses
+                /// (`CR2.ADDM7`). The receiver is muted immediately.
+                ///
+                /// An address character is marked by its most significant bit:
+                /// the 9th bit with [`Config::wordlength_9`], or the 8th bit
+                /// otherwise. With 8-bit words, this leaves only 7 bits for
+                /// data bytes, as any byte with the 8th bit set is treated as
+                /// an address. A character with the address mark is sent by
+                /// [`Serial::send_address`].
+                ///
+                /// Once an address character that matches `address` has been
+                /// received, the receiver is unmuted, and receives the
+                /// address character itself, followed by all data until the
+                /// next address character. If that doesn't match, the receiver
+                /// is muted again.
+                ///
+                /// Only the lower 7 bits of `address` are used.
+                pub fn enable_address_filter(&mut self, address: u8) {
+                    let address = u32::from(address & 0x7f);
+
+                    // `CR2.ADD`, `CR2.ADDM7` and `CR1.WAKE` can only be
+                    // written while the USART is disabled. These bits are
+                    // written directly, as their register API differs between
+                    // the USARTs.
+                    self.usart.cr1.modify(|_, w| w.ue().clear_bit());
+                    self.usart.cr2.modify(|r, w| unsafe {
+                        // ADD, ADDM7
+                        w.bits(r.bits() & !0xff00_0010 | address << 24 | 1 << 4)
+                    });
+                    self.usart.cr1.modify(|r, w| unsafe {
+                        // MME, WAKE
+                        w.bits(r.bits() | 1 << 13 | 1 << 11)
+                    });
+                    self.usart.cr1.modify(|_, w| w.ue().set_bit());
+
+                    self.mute();
+                }
+
+                /// Disables mute mode (`CR1.MME`)
+                ///
+                /// All data is received again, regardless of its address.
+                pub fn disable_address_filter(&mut self) {
+                    self.usart.cr1.modify(|r, w| unsafe { w.bits(r.bits() & !(1 << 13)) });
+                }
+
+                /// Mutes the receiver until the next matching address
+                /// (`RQR.MMRQ`)
+                ///
+                /// Requires [`Serial::enable_address_filter`].
+                pub fn mute(&mut self) {
+                    self.usart.rqr.write(|w| unsafe { w.bits(1 << 2) });
+                }
+
+                /// Indicates whether the receiver is muted (`ISR.RWU`)
+                pub fn is_muted(&self) -> bool {
+                    self.usart.isr.read().bits() & (1 << 19) != 0
+                }
+
+                /// Sends an address character, marked by its most significant
+                /// bit
+                ///
+                /// Wakes up the node that was configured with the same address
+                /// using [`Serial::enable_address_filter`], and mutes all
+                /// others. Only the lower 7 bits of `address` are used.
+                pub fn send_address(&mut self, address: u8) -> nb::Result<(), Error> {
+                    let address = address & 0x7f;
+
+                    if self.usart.cr1.read().m0().bit_is_set() {
+                        self.tx.write_with_flag(address, true)
+                    } else {
+                        self.tx.write_u16(u16::from(address | 0x80))
+                    }
                 }
 
                 /// Starts listening for an interrupt event
@@ -468,6 +927,20 @@ macro_rules! usart {
                     self.rx.clear_errors()
                 }
 
+                /// Writes as many bytes from `buf` as possible without blocking
+                ///
+                /// See [`Tx::write_nonblocking`] for details.
+                pub fn write_nonblocking(&mut self, buf: &[u8]) -> usize {
+                    self.tx.write_nonblocking(buf)
+                }
+
+                /// Blocks until the last byte has been shifted out completely
+                ///
+                /// See [`Tx::flush_blocking`] for details.
+                pub fn flush_blocking(&mut self) {
+                    self.tx.flush_blocking()
+                }
+
                 pub fn split(self) -> (Tx<$USARTX>, Rx<$USARTX>) {
                     (self.tx, self.rx)
                 }
@@ -477,6 +950,140 @@ macro_rules! usart {
                 }
             }
 
+            impl Serial<$USARTX> {
+                /// Turns this `Serial` into a DMX512 receiver
+                ///
+                /// The `Serial` should be configured using [`Config::dmx512`].
+                pub fn into_dmx_receiver(self) -> DmxReceiver<$USARTX> {
+                    DmxReceiver {
+                        serial: self,
+                        buffer: [0; DMX_FRAME_LEN],
+                        len: 0,
+                        in_frame: false,
+                    }
+                }
+            }
+
+            impl DmxReceiver<$USARTX> {
+                /// Processes the received data and returns a frame, if complete
+                ///
+                /// Returns `WouldBlock`, if no frame has been completed yet.
+                /// Call this often enough (or from the USART interrupt) to not
+                /// miss any bytes, as the USART has no receive FIFO.
+                ///
+                /// Any reception error, other than a break, discards the
+                /// current frame.
+                pub fn read_frame(&mut self) -> nb::Result<&[u8], Error> {
+                    loop {
+                        let isr = self.serial.usart.isr.read();
+
+                        if isr.fe().bit_is_set() {
+                            // NOTE(read_volatile) see `write_volatile` above
+                            let byte: u8 = unsafe {
+                                ptr::read_volatile(&self.serial.usart.rdr as *const _ as *const _)
+                            };
+                            self.serial.usart.icr.write(|w| w.fecf().set_bit());
+
+                            if byte != 0 {
+                                self.in_frame = false;
+                                self.len = 0;
+                                return Err(nb::Error::Other(Error::Framing));
+                            }
+
+                            // Break: the current frame ends, the next one starts
+                            let len = self.len;
+                            let complete = self.in_frame && len > 0;
+                            self.in_frame = true;
+                            self.len = 0;
+
+                            if complete {
+                                return Ok(&self.buffer[..len]);
+                            }
+                            continue;
+                        }
+
+                        if isr.ore().bit_is_set() || isr.nf().bit_is_set() || isr.pe().bit_is_set() {
+                            self.in_frame = false;
+                            self.len = 0;
+                            self.serial.check_errors()?;
+                        }
+
+                        if isr.rxne().bit_is_clear() {
+                            return Err(nb::Error::WouldBlock);
+                        }
+
+                        // NOTE(read_volatile) see `write_volatile` above
+                        let byte: u8 = unsafe {
+                            ptr::read_volatile(&self.serial.usart.rdr as *const _ as *const _)
+                        };
+
+                        if self.in_frame {
+                            self.buffer[self.len] = byte;
+                            self.len += 1;
+
+                            if self.len == DMX_FRAME_LEN {
+                                self.in_frame = false;
+                                self.len = 0;
+                                return Ok(&self.buffer[..]);
+                            }
+                        }
+                    }
+                }
+
+                /// Returns the underlying `Serial`
+                pub fn release(self) -> Serial<$USARTX> {
+                    self.serial
+                }
+            }
+
+            impl Serial<$USARTX> {
+                /// Reads a full 9-bit word, see [`Rx::read_u16`]
+                pub fn read_u16(&mut self) -> nb::Result<u16, Error> {
+                    self.rx.read_u16()
+                }
+
+                /// Reads a byte and the 9th bit, see [`Rx::read_with_flag`]
+                pub fn read_with_flag(&mut self) -> nb::Result<(u8, bool), Error> {
+                    self.rx.read_with_flag()
+                }
+
+                /// Reads a line, see [`Rx::read_line`]
+                pub fn read_line(&mut self, buf: &mut [u8], terminator: u8) -> Result<usize, Error> {
+                    self.rx.read_line(buf, terminator)
+                }
+
+                /// Reads a line, configured by `config`
+                ///
+                /// Works like [`Rx::read_line`], with the terminator given by
+                /// `config`. If enabled, a `b'\r'` before the terminator is
+                /// removed, and every received byte is written back before
+                /// it is stored.
+                pub fn read_line_with(
+                    &mut self,
+                    buf: &mut [u8],
+                    config: LineConfig,
+                ) -> Result<usize, Error> {
+                    let tx = &mut self.tx;
+                    let echo = config.echo;
+                    self.rx.read_line_inner(buf, config, |byte| {
+                        if echo {
+                            block!(hal::serial::Write::write(tx, byte))?;
+                        }
+                        Ok(())
+                    })
+                }
+
+                /// Writes a full 9-bit word, see [`Tx::write_u16`]
+                pub fn write_u16(&mut self, word: u16) -> nb::Result<(), Error> {
+                    self.tx.write_u16(word)
+                }
+
+                /// Writes a byte and the 9th bit, see [`Tx::write_with_flag`]
+                pub fn write_with_flag(&mut self, byte: u8, flag: bool) -> nb::Result<(), Error> {
+                    self.tx.write_with_flag(byte, flag)
+                }
+            }
+
             impl hal::serial::Read<u8> for Serial<$USARTX> {
                 type Error = Error;
 
@@ -498,6 +1105,26 @@ macro_rules! usart {
             }
 
             impl Rx<$USARTX> {
+                /// Initializes the USART for receiving only
+                ///
+                /// Only the RX pin is required and only the receiver is
+                /// enabled (`CR1.RE`), so the pin that would otherwise be used
+                /// for transmitting stays available. The USART peripheral is
+                /// consumed.
+                pub fn new<RX>(
+                    usart: $USARTX,
+                    rx: RX,
+                    config: Config,
+                    rcc: &mut Rcc,
+                ) -> Result<Self, InvalidConfig>
+                where
+                    RX: RxPin<$USARTX>,
+                {
+                    rx.setup();
+                    Serial::<$USARTX>::configure(&usart, &config, rcc, false, true)?;
+                    Ok(Rx { _usart: PhantomData })
+                }
+
                 /// Checks for reception errors that may have occurred.
                 ///
                 /// Note that multiple errors can be signaled at the same time. In that case,
@@ -538,6 +1165,157 @@ macro_rules! usart {
                 }
             }
 
+            impl HalfDuplex<$USARTX> {
+                /// Initializes the USART for single-wire half-duplex operation
+                ///
+                /// Only the TX pin is required, as it is used in both
+                /// directions. The pin should be configured as an open-drain
+                /// output (see `into_open_drain_output`), with a pull-up on the
+                /// line, so devices don't drive against each other.
+                pub fn new<TX>(
+                    usart: $USARTX,
+                    tx: TX,
+                    config: Config,
+                    rcc: &mut Rcc,
+                ) -> Result<Self, InvalidConfig>
+                where
+                    TX: TxPin<$USARTX>,
+                {
+                    tx.setup();
+                    Serial::<$USARTX>::configure(&usart, &config, rcc, true, true)?;
+
+                    // `HDSEL` can only be written while the USART is disabled
+                    usart.cr1.modify(|_, w| w.ue().clear_bit());
+                    usart.cr3.modify(|_, w| w.hdsel().set_bit());
+                    usart.cr1.modify(|_, w| w.ue().set_bit());
+
+                    Ok(HalfDuplex {
+                        usart,
+                        rx: Rx { _usart: PhantomData },
+                        tx: Tx { _usart: PhantomData },
+                    })
+                }
+
+                /// Releases the USART peripheral
+                pub fn release(self) -> $USARTX {
+                    self.usart
+                }
+            }
+
+            impl hal::serial::Read<u8> for HalfDuplex<$USARTX> {
+                type Error = Error;
+
+                fn read(&mut self) -> nb::Result<u8, Error> {
+                    self.rx.read()
+                }
+            }
+
+            impl hal::serial::Write<u8> for HalfDuplex<$USARTX> {
+                type Error = Error;
+
+                fn flush(&mut self) -> nb::Result<(), Self::Error> {
+                    if self.usart.cr1.read().re().bit_is_set() {
+                        return Ok(());
+                    }
+
+                    self.tx.flush()?;
+
+                    // Transmission complete, listen on the line again
+                    self.usart.cr1.modify(|_, w| w.re().set_bit());
+                    Ok(())
+                }
+
+                fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+                    // Don't receive our own transmission
+                    if self.usart.cr1.read().re().bit_is_set() {
+                        self.usart.cr1.modify(|_, w| w.re().clear_bit());
+                    }
+
+                    self.tx.write(byte)
+                }
+            }
+
+            impl Rx<$USARTX> {
+                /// Turns this `Rx` into an interrupt-driven, buffered receiver
+                ///
+                /// The RXNE interrupt is enabled. `watermark_percent` sets the
+                /// buffer level, as a percentage of the buffer's size, at which
+                /// the watermark is reached.
+                ///
+                /// # Panics
+                ///
+                /// Panics, if `buffer` is empty or `watermark_percent` isn't
+                /// in the range of 1 to 100.
+                pub fn into_buffered(
+                    self,
+                    buffer: &'static mut [u8],
+                    watermark_percent: u8,
+                ) -> BufferedRx<$USARTX> {
+                    assert!(!buffer.is_empty());
+                    assert!(watermark_percent > 0 && watermark_percent <= 100);
+
+                    let watermark = core::cmp::max(
+                        buffer.len() * usize::from(watermark_percent) / 100,
+                        1,
+                    );
+
+                    // NOTE(unsafe) atomic write to a register owned by `Rx`
+                    unsafe {
+                        (*$USARTX::ptr()).cr1.modify(|_, w| w.rxneie().set_bit());
+                    }
+
+                    BufferedRx {
+                        rx: self,
+                        buffer,
+                        start: 0,
+                        len: 0,
+                        watermark,
+                        above_watermark: false,
+                    }
+                }
+            }
+
+            impl BufferedRx<$USARTX> {
+                /// Moves all received bytes into the buffer
+                ///
+                /// Call this from the USART interrupt handler. Returns
+                /// `Ok(true)`, if the watermark was reached during this call,
+                /// so the caller can act on it right away.
+                ///
+                /// Returns [`Error::Overrun`], if the buffer is full and a
+                /// received byte had to be dropped. Reception errors are
+                /// returned as well. In both cases, the remaining bytes are
+                /// processed on the next call.
+                pub fn on_interrupt(&mut self) -> Result<bool, Error> {
+                    let was_above_watermark = self.above_watermark;
+
+                    loop {
+                        match hal::serial::Read::read(&mut self.rx) {
+                            Ok(byte) => {
+                                self.push(byte)?;
+                                if self.len >= self.watermark {
+                                    self.above_watermark = true;
+                                }
+                            }
+                            Err(nb::Error::WouldBlock) => break,
+                            Err(nb::Error::Other(error)) => return Err(error),
+                        }
+                    }
+
+                    Ok(self.above_watermark && !was_above_watermark)
+                }
+
+                /// Disables the RXNE interrupt and returns the `Rx` and buffer
+                pub fn release(self) -> (Rx<$USARTX>, &'static mut [u8]) {
+                    // NOTE(unsafe) atomic write to a register owned by `Rx`
+                    unsafe {
+                        (*$USARTX::ptr()).cr1.modify(|_, w| w.rxneie().clear_bit());
+                    }
+
+                    (self.rx, self.buffer)
+                }
+            }
+
             /// DMA operations.
             #[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
             impl Rx<$USARTX> {
@@ -593,6 +1371,85 @@ macro_rules! usart {
                 }
             }
 
+            impl Rx<$USARTX> {
+                /// Reads a full 9-bit word
+                ///
+                /// Use this with [`Config::wordlength_9`]. If parity is
+                /// enabled, the 9th bit is the parity bit.
+                pub fn read_u16(&mut self) -> nb::Result<u16, Error> {
+                    self.check_errors()?;
+
+                    // NOTE(unsafe) atomic read with no side effects
+                    let isr = unsafe { (*$USARTX::ptr()).isr.read() };
+
+                    if isr.rxne().bit_is_set() {
+                        // NOTE(unsafe) atomic read from stateless register
+                        Ok(unsafe { (*$USARTX::ptr()).rdr.read().rdr().bits() } & 0x1ff)
+                    } else {
+                        Err(nb::Error::WouldBlock)
+                    }
+                }
+
+                /// Reads a byte and the 9th bit separately
+                ///
+                /// Multidrop protocols use the 9th bit to mark address bytes.
+                pub fn read_with_flag(&mut self) -> nb::Result<(u8, bool), Error> {
+                    let word = self.read_u16()?;
+                    Ok((word as u8, word & 0x100 != 0))
+                }
+
+                /// Reads a line, until `terminator` is received or `buf` is full
+                ///
+                /// Blocks until `terminator` (usually `b'\n'`) is received,
+                /// and returns the number of bytes written to `buf`,
+                /// excluding the terminator. The bytes are stored as
+                /// received, see [`Serial::read_line_with`] for removing a
+                /// `b'\r'`.
+                ///
+                /// Returns [`Error::BufferFull`] as soon as `buf` is full
+                /// without the terminator. Then, `buf` holds the start of the
+                /// line, and the rest of it is left to be read. A receive
+                /// error is returned right away, and the bytes read until
+                /// then are lost.
+                pub fn read_line(&mut self, buf: &mut [u8], terminator: u8) -> Result<usize, Error> {
+                    let config = LineConfig::default().terminator(terminator);
+                    self.read_line_inner(buf, config, |_| Ok(()))
+                }
+
+                fn read_line_inner<F>(
+                    &mut self,
+                    buf: &mut [u8],
+                    config: LineConfig,
+                    mut echo: F,
+                ) -> Result<usize, Error>
+                where
+                    F: FnMut(u8) -> Result<(), Error>,
+                {
+                    let mut len = 0;
+
+                    loop {
+                        if len == buf.len() {
+                            return Err(Error::BufferFull);
+                        }
+
+                        let byte = block!(hal::serial::Read::read(self))?;
+                        echo(byte)?;
+
+                        if byte == config.terminator {
+                            break;
+                        }
+                        buf[len] = byte;
+                        len += 1;
+                    }
+
+                    if config.strip_cr && len > 0 && buf[len - 1] == b'\r' {
+                        len -= 1;
+                    }
+
+                    Ok(len)
+                }
+            }
+
             impl hal::serial::Read<u8> for Rx<$USARTX> {
                 type Error = Error;
 
@@ -615,6 +1472,92 @@ macro_rules! usart {
                 }
             }
 
+            impl Tx<$USARTX> {
+                /// Initializes the USART for transmitting only
+                ///
+                /// Only the TX pin is required and only the transmitter is
+                /// enabled (`CR1.TE`), so the pin that would otherwise be used
+                /// for receiving stays available. The USART peripheral is
+                /// consumed.
+                pub fn new<TX>(
+                    usart: $USARTX,
+                    tx: TX,
+                    config: Config,
+                    rcc: &mut Rcc,
+                ) -> Result<Self, InvalidConfig>
+                where
+                    TX: TxPin<$USARTX>,
+                {
+                    tx.setup();
+                    Serial::<$USARTX>::configure(&usart, &config, rcc, true, false)?;
+                    Ok(Tx { _usart: PhantomData })
+                }
+
+                /// Blocks until the last byte has been shifted out completely
+                ///
+                /// This waits for the transmit data register to be empty
+                /// (`TXE`) and then for the transmission to be complete (`TC`),
+                /// which means the stop bit of the last byte has left the shift
+                /// register. Use this before entering Stop mode or disabling
+                /// the USART, as waiting for `TXE` alone would truncate the
+                /// last character.
+                ///
+                /// Like [`hal::serial::Write::flush`], this leaves `TC` set, so
+                /// both return immediately if nothing has been written since.
+                pub fn flush_blocking(&mut self) {
+                    // NOTE(unsafe) atomic reads with no side effects
+                    while unsafe { (*$USARTX::ptr()).isr.read() }.txe().bit_is_clear() {}
+                    while unsafe { (*$USARTX::ptr()).isr.read() }.tc().bit_is_clear() {}
+                }
+
+                /// Writes as many bytes from `buf` as possible without blocking
+                ///
+                /// Bytes are written as long as the transmit data register is
+                /// empty (`TXE`). Returns the number of bytes that were
+                /// written. Call this again with the remainder of `buf`
+                /// (`&buf[n..]`) to continue the transmission.
+                pub fn write_nonblocking(&mut self, buf: &[u8]) -> usize {
+                    let mut written = 0;
+                    for &byte in buf {
+                        match hal::serial::Write::write(self, byte) {
+                            Ok(()) => written += 1,
+                            Err(_) => break,
+                        }
+                    }
+                    written
+                }
+            }
+
+            impl Tx<$USARTX> {
+                /// Writes a full 9-bit word
+                ///
+                /// Use this with [`Config::wordlength_9`] and parity
+                /// disabled. Otherwise, the 9th bit is ignored.
+                pub fn write_u16(&mut self, word: u16) -> nb::Result<(), Error> {
+                    // NOTE(unsafe) atomic read with no side effects
+                    let isr = unsafe { (*$USARTX::ptr()).isr.read() };
+
+                    if isr.txe().bit_is_set() {
+                        // NOTE(unsafe) atomic write to stateless register
+                        #[allow(unused_unsafe)]
+                        unsafe {
+                            (*$USARTX::ptr()).tdr.write(|w| w.tdr().bits(word & 0x1ff))
+                        }
+
+                        Ok(())
+                    } else {
+                        Err(nb::Error::WouldBlock)
+                    }
+                }
+
+                /// Writes a byte with the 9th bit set to `flag`
+                ///
+                /// Multidrop protocols set the 9th bit to mark address bytes.
+                pub fn write_with_flag(&mut self, byte: u8, flag: bool) -> nb::Result<(), Error> {
+                    self.write_u16(u16::from(byte) | if flag { 0x100 } else { 0 })
+                }
+            }
+
             impl hal::serial::Write<u8> for Tx<$USARTX> {
                 type Error = Error;
 
@@ -622,12 +1565,9 @@ macro_rules! usart {
                     // NOTE(unsafe) atomic read with no side effects
                     let isr = unsafe { (*$USARTX::ptr()).isr.read() };
 
-                    // Frame complete, set the TC Clear Flag
-                    unsafe {
-                        (*$USARTX::ptr()).icr.write(|w| {w.tccf().set_bit()});
-                    }
-
-                    // Check TC bit on ISR
+                    // Check TC bit on ISR. It's cleared by the next write to
+                    // TDR, so it must not be cleared here. Otherwise, a later
+                    // flush would wait for a transmission that never happens.
                     if isr.tc().bit_is_set() {
                         Ok(())
                     } else {
@@ -716,21 +1656,21 @@ macro_rules! usart {
     feature = "io-STM32L071",
 ))]
 usart! {
-    LPUART1: (lpuart1, apb1enr, lpuart1en, apb1_clk, Serial1LpExt),
-    USART2: (usart2, apb1enr, usart2en, apb1_clk, Serial2Ext),
+    LPUART1: (lpuart1, apb1enr, lpuart1en, Some(ClockedPeripheral::LPUART1), Serial1LpExt),
+    USART2: (usart2, apb1enr, usart2en, Some(ClockedPeripheral::USART2), Serial2Ext),
 }
 
 // USART1 is available on category 3/5 MCUs
 #[cfg(any(feature = "io-STM32L051", feature = "io-STM32L071"))]
 usart! {
-    USART1: (usart1, apb2enr, usart1en, apb1_clk, Serial1Ext),
+    USART1: (usart1, apb2enr, usart1en, Some(ClockedPeripheral::USART1), Serial1Ext),
 }
 
 // USART4 and USART5 are available on category 5 MCUs
 #[cfg(feature = "io-STM32L071")]
 usart! {
-    USART4: (usart4, apb1enr, usart4en, apb1_clk, Serial4Ext),
-    USART5: (usart5, apb1enr, usart5en, apb1_clk, Serial5Ext),
+    USART4: (usart4, apb1enr, usart4en, None, Serial4Ext),
+    USART5: (usart5, apb1enr, usart5en, None, Serial5Ext),
 }
 
 impl Serial<LPUART1> {
@@ -743,6 +1683,9 @@ impl Serial<LPUART1> {
     /// and LPUART1 is forced to 9600 when clocked by LSE,
     /// assuming that LSE is 32768
     /// (and it must be so according to RM).
+    ///
+    /// To use the configured baudrate instead, select LSE using
+    /// [`Rcc::set_clock_source`] before initializing LPUART1.
     pub fn use_lse(&mut self, rcc: &mut Rcc, _: &LSE) {
         //Disable transmitter
         self.usart.cr1.modify(|_, w| w.te().disabled());
@@ -752,7 +1695,7 @@ impl Serial<LPUART1> {
         self.usart.cr1.modify(|_, w| w.ue().disabled());
 
         //Reconfigure LPUART to use LSE
-        rcc.rb.ccipr.modify(|_, w| w.lpuart1sel().lse());
+        rcc.set_clock_source(ClockedPeripheral::LPUART1, PeripheralClockSrc::LSE);
 
         //Recalculate baudrate
         //TODO requested baudrate value from the config should be stored somehow and used here
@@ -49,6 +49,9 @@ pub trait PinMiso<SPI> {
 pub trait PinMosi<SPI> {
     fn setup(&self);
 }
+pub trait PinNss<SPI> {
+    fn setup(&self);
+}
 
 impl<SPI, SCK, MISO, MOSI> Pins<SPI> for (SCK, MISO, MOSI)
 where
@@ -83,7 +86,8 @@ macro_rules! pins {
     ($($SPIX:ty:
         SCK: [$([$SCK:ty, $ALTMODESCK:path]),*]
         MISO: [$([$MISO:ty, $ALTMODEMISO:path]),*]
-        MOSI: [$([$MOSI:ty, $ALTMODEMOSI:path]),*])+) => {
+        MOSI: [$([$MOSI:ty, $ALTMODEMOSI:path]),*]
+        NSS: [$([$NSS:ty, $ALTMODENSS:path]),*])+) => {
         $(
             $(
                 impl PinSck<$SPIX> for $SCK {
@@ -106,6 +110,13 @@ macro_rules! pins {
                     }
                 }
             )*
+            $(
+                impl PinNss<$SPIX> for $NSS {
+                    fn setup(&self) {
+                        self.set_alt_mode($ALTMODENSS);
+                    }
+                }
+            )*
         )+
     }
 }
@@ -130,6 +141,10 @@ pins! {
             [PA12<Analog>, AltMode::AF0],
             [PB5<Analog>, AltMode::AF0]
         ]
+        NSS: [
+            [PA4<Analog>, AltMode::AF0],
+            [PA15<Analog>, AltMode::AF0]
+        ]
 }
 
 #[cfg(any(feature = "stm32l0x2", feature = "stm32l0x3"))]
@@ -147,6 +162,9 @@ pins! {
             [NoMosi, None],
             [PB15<Analog>, AltMode::AF0]
         ]
+        NSS: [
+            [PB12<Analog>, AltMode::AF0]
+        ]
 }
 
 #[cfg(feature = "stm32l0x1")]
@@ -164,6 +182,10 @@ pins! {
             [NoMosi, None],
             [PA7<Analog>, AltMode::AF0]
         ]
+        NSS: [
+            [PA4<Analog>, AltMode::AF0],
+            [PA15<Analog>, AltMode::AF0]
+        ]
 }
 
 #[derive(Debug)]
@@ -262,6 +284,94 @@ macro_rules! spi {
                     (self.spi, self.pins)
                 }
 
+                /// Switches to the TI synchronous serial frame format
+                ///
+                /// In TI mode (`CR2.FRF`), the hardware drives `nss` itself
+                /// and pulses it high for one clock cycle before each frame,
+                /// instead of holding it low for the whole transaction. This is
+                /// required by some devices, like audio codecs, that use TI's
+                /// frame format.
+                ///
+                /// The clock polarity and phase are fixed by the TI protocol:
+                /// data is shifted out on the rising edge and sampled on the
+                /// falling edge of SCK. The `mode` passed to the constructor is
+                /// ignored by the peripheral while TI mode is active. For the
+                /// same reason, software chip select, for example using
+                /// [`with_cs`](#method.with_cs), is not available in this mode.
+                ///
+                /// `nss` is returned together with the other pins by
+                /// [`free`](#method.free).
+                pub fn into_ti_mode<NSS>(self, nss: NSS) -> Spi<$SPIX, (PINS, NSS)>
+                where
+                    NSS: PinNss<$SPIX>,
+                {
+                    nss.setup();
+
+                    // The frame format must only be changed while the SPI is
+                    // disabled.
+                    while self.spi.sr.read().bsy().bit_is_set() {}
+                    self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+                    self.spi.cr2.modify(|_, w| w.frf().set_bit());
+                    self.spi.cr1.modify(|_, w| w.spe().set_bit());
+
+                    Spi {
+                        spi: self.spi,
+                        pins: (self.pins, nss),
+                    }
+                }
+
+                /// Runs `f` as a transaction with the chip select pin `cs`
+                ///
+                /// `cs` is driven low before `f` is called. After `f` returns,
+                /// this waits until the SPI is no longer busy, then drives `cs`
+                /// high again. `cs` is also released if `f` panics.
+                pub fn with_cs<CS, F, R>(&mut self, cs: &mut CS, f: F) -> R
+                where
+                    CS: hal::digital::v2::OutputPin,
+                    F: FnOnce(&mut Self) -> R,
+                {
+                    self.with_cs_delay(cs, 0, f)
+                }
+
+                /// Like [`with_cs`](#method.with_cs), with setup and hold times
+                ///
+                /// Waits for at least `cycles` core clock cycles after asserting
+                /// `cs` and before deasserting it.
+                pub fn with_cs_delay<CS, F, R>(&mut self, cs: &mut CS, cycles: u32, f: F) -> R
+                where
+                    CS: hal::digital::v2::OutputPin,
+                    F: FnOnce(&mut Self) -> R,
+                {
+                    struct Deassert<'a, CS: hal::digital::v2::OutputPin> {
+                        cs: &'a mut CS,
+                        cycles: u32,
+                    }
+
+                    impl<'a, CS: hal::digital::v2::OutputPin> Drop for Deassert<'a, CS> {
+                        fn drop(&mut self) {
+                            // Safe, as we're only doing atomic reads.
+                            let spi = unsafe { &*$SPIX::ptr() };
+                            while spi.sr.read().bsy().bit_is_set() {}
+
+                            if self.cycles > 0 {
+                                cortex_m::asm::delay(self.cycles);
+                            }
+                            let _ = self.cs.set_high();
+                        }
+                    }
+
+                    let _ = cs.set_low();
+                    if cycles > 0 {
+                        cortex_m::asm::delay(cycles);
+                    }
+
+                    // This will deassert CS when dropped (so either when
+                    // `with_cs_delay` returns or when `f` unwinds)
+                    let _guard = Deassert { cs, cycles };
+
+                    f(self)
+                }
+
                 pub fn read_all<Channel, Buffer>(
                     self,
                     dma:     &mut dma::Handle,
@@ -13,8 +13,7 @@ pub struct SYSCFG {
 impl SYSCFG {
     pub fn new(syscfg: PacSyscfg, rcc: &mut Rcc) -> Self {
         // Reset SYSCFG peripheral
-        rcc.rb.apb2rstr.modify(|_, w| w.syscfgrst().set_bit());
-        rcc.rb.apb2rstr.modify(|_, w| w.syscfgrst().clear_bit());
+        rcc.reset::<PacSyscfg>();
 
         // Enable SYSCFG peripheral
         rcc.rb.apb2enr.modify(|_, w| w.syscfgen().set_bit());
@@ -3,7 +3,6 @@ use crate::hal::timer::{CountDown, Periodic};
 use crate::pac::{tim2, tim21, tim22, tim6, TIM2, TIM21, TIM22, TIM3, TIM6};
 use crate::rcc::{Clocks, Rcc};
 use crate::time::Hertz;
-use cast::{u16, u32};
 use cortex_m::peripheral::syst::SystClkSource;
 use cortex_m::peripheral::SYST;
 use void::Void;
@@ -81,6 +80,35 @@ impl TimerExt<SYST> for SYST {
 
 impl Periodic for Timer<SYST> {}
 
+/// Computes the prescaler and auto-reload values for a timer frequency
+///
+/// Returns the register values `(PSC, ARR)`, so that a timer clocked at
+/// `clk` overflows at a rate as close to `target` as possible. The timer
+/// counts `(PSC + 1) * (ARR + 1)` clock cycles per period. The prescaler is
+/// kept as small as possible, to maximize the resolution of the counter.
+///
+/// Returns `None`, if `target` is zero, higher than half of `clk` or too low
+/// to be reached with 16 bit prescaler and auto-reload values.
+pub fn compute_psc_arr(clk: Hertz, target: Hertz) -> Option<(u16, u16)> {
+    // The period must be at least two clock cycles long
+    if target.0 == 0 || target.0 > clk.0 / 2 {
+        return None;
+    }
+
+    // Round to the nearest number of clock cycles per period
+    let ticks = (u64::from(clk.0) + u64::from(target.0) / 2) / u64::from(target.0);
+
+    let psc = (ticks - 1) / (1 << 16);
+    if psc > u64::from(u16::MAX) {
+        return None;
+    }
+
+    let divider = psc + 1;
+    let arr = (ticks + divider / 2) / divider - 1;
+
+    Some((psc as u16, arr as u16))
+}
+
 /// Trait for general purpose timer peripherals
 pub trait GeneralPurposeTimer {
     type MasterMode;
@@ -101,8 +129,116 @@ impl<T: GeneralPurposeTimer> Timer<T> {
     }
 }
 
+/// Timer input that clocks the counter in external clock mode 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockInput {
+    /// Filtered timer input 1 (`TI1FP1`)
+    Ti1,
+    /// Filtered timer input 2 (`TI2FP2`)
+    Ti2,
+    /// Both edges of timer input 1 (`TI1F_ED`)
+    ///
+    /// The polarity is ignored for this input.
+    Ti1BothEdges,
+}
+
+/// Active edge of an external clock input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockPolarity {
+    /// Count on rising edges
+    Rising,
+    /// Count on falling edges
+    Falling,
+}
+
+/// Prescaler of the external trigger input (`SMCR.ETPS`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EtrPrescaler {
+    Div1 = 0b00,
+    Div2 = 0b01,
+    Div4 = 0b10,
+    Div8 = 0b11,
+}
+
+/// Configuration of the external trigger input (ETR) for external clock
+/// mode 2
+///
+/// The filter value selects the sampling frequency and the number of
+/// consecutive samples needed to validate an edge, see the description of
+/// `SMCR.ETF` in the reference manual. `0` disables the filter.
+#[derive(Clone, Copy, Debug)]
+pub struct EtrConfig {
+    pub polarity: ClockPolarity,
+    pub prescaler: EtrPrescaler,
+    pub filter: u8,
+}
+
+impl EtrConfig {
+    pub fn polarity(mut self, polarity: ClockPolarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    /// Sets the prescaler of the ETR input
+    ///
+    /// The prescaled signal must be slower than a quarter of the timer clock.
+    pub fn prescaler(mut self, prescaler: EtrPrescaler) -> Self {
+        self.prescaler = prescaler;
+        self
+    }
+
+    /// Sets the digital filter of the ETR input (`0`-`15`)
+    pub fn filter(mut self, filter: u8) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+impl Default for EtrConfig {
+    fn default() -> Self {
+        EtrConfig {
+            polarity: ClockPolarity::Rising,
+            prescaler: EtrPrescaler::Div1,
+            filter: 0,
+        }
+    }
+}
+
+/// Snapshot of the status flags of a timer (`SR`)
+///
+/// Flags for channels a timer doesn't have are always `false`. The timers of
+/// the STM32L0 don't have a break input, so there is no break flag.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimerStatus {
+    /// Update event (`UIF`)
+    pub update: bool,
+    /// Capture or compare event on channels 1 to 4 (`CCxIF`)
+    pub capture_compare: [bool; 4],
+    /// Trigger event (`TIF`)
+    pub trigger: bool,
+    /// Capture overcapture on channels 1 to 4 (`CCxOF`)
+    ///
+    /// A capture occurred while the capture flag of the channel was still
+    /// set, so the previous captured value was lost.
+    pub overcapture: [bool; 4],
+}
+
+impl TimerStatus {
+    /// Decodes the value of an `SR` register
+    pub(crate) fn from_bits(bits: u32) -> Self {
+        let bit = |i: u32| bits & (1 << i) != 0;
+
+        TimerStatus {
+            update: bit(0),
+            capture_compare: [bit(1), bit(2), bit(3), bit(4)],
+            trigger: bit(6),
+            overcapture: [bit(9), bit(10), bit(11), bit(12)],
+        }
+    }
+}
+
 macro_rules! timers {
-    ($($TIM:ident: ($tim:ident, $timXen:ident, $timXrst:ident, $apbenr:ident, $apbrstr:ident, $timclk:ident, $mms:ty),)+) => {
+    ($($TIM:ident: ($tim:ident, $timXen:ident, $apbenr:ident, $timclk:ident, $mms:ty),)+) => {
         $(
             impl TimerExt<$TIM> for $TIM {
                 fn timer<T>(self, timeout: T, rcc: &mut Rcc) -> Timer<$TIM>
@@ -139,6 +275,18 @@ macro_rules! timers {
                     self.tim.sr.write(|w| w.uif().clear_bit());
                 }
 
+                /// Returns the status flags, for diagnostics
+                pub fn status(&self) -> TimerStatus {
+                    TimerStatus::from_bits(self.tim.sr.read().bits())
+                }
+
+                /// Clears all status flags
+                pub fn clear_flags(&mut self) {
+                    // The flags are cleared by writing 0
+                    #[allow(unused_unsafe)]
+                    self.tim.sr.write(|w| unsafe { w.bits(0) });
+                }
+
                 /// Releases the TIM peripheral
                 pub fn release(self) -> $TIM {
                     self.tim.cr1.modify(|_, w| w.cen().clear_bit());
@@ -155,6 +303,91 @@ macro_rules! timers {
                     self.tim.cr1.modify(|_, w| w.cen().set_bit());
                 }
 
+                /// Returns the current counter value
+                ///
+                /// All timers on the STM32L0 are 16 bits wide, including TIM2
+                /// and TIM3. Use [`LinkedTimerPair`] for a 32 bit counter.
+                pub fn counter(&self) -> u16 {
+                    self.tim.cnt.read().cnt().bits()
+                }
+
+                /// Resets the counter by generating an update event (`EGR.UG`)
+                ///
+                /// This also reloads the prescaler. Unlike [`reset`], the
+                /// counter keeps running. If `CR1.URS` is cleared, this also
+                /// sets the update interrupt flag.
+                ///
+                /// [`reset`]: #method.reset
+                pub fn reset_count(&mut self) {
+                    self.tim.egr.write(|w| w.ug().set_bit());
+                }
+
+                /// Blocks for the given number of microseconds
+                ///
+                /// The prescaler is set so the timer ticks at 1 MHz, if the
+                /// timer clock allows it. Delays longer than a single
+                /// auto-reload period are split into multiple periods.
+                ///
+                /// This reprograms the prescaler and the auto-reload register
+                /// and leaves the counter stopped, so any previously configured
+                /// timeout has to be restarted using `start`.
+                pub fn delay_us(&mut self, us: u32) {
+                    self.delay(u64::from(us));
+                }
+
+                /// Blocks for the given number of milliseconds
+                ///
+                /// See [`delay_us`](#method.delay_us) for details.
+                pub fn delay_ms(&mut self, ms: u32) {
+                    self.delay(u64::from(ms) * 1_000);
+                }
+
+                fn delay(&mut self, us: u64) {
+                    let clk = self.clocks.$timclk().0;
+                    let psc = (clk / 1_000_000).saturating_sub(1);
+                    let tick_hz = clk / (psc + 1);
+                    let mut ticks = us * u64::from(tick_hz) / 1_000_000;
+
+                    // pause
+                    self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+                    self.tim.psc.write(|w| w.psc().bits(psc as u16));
+
+                    while ticks > 0 {
+                        let period = delay_chunk(ticks);
+                        ticks = ticks.saturating_sub(period);
+
+                        // This is only unsafe for some timers, so we need this to
+                        // suppress the warnings.
+                        #[allow(unused_unsafe)]
+                        self.tim.arr.write(|w|
+                            unsafe {
+                                w.arr().bits((period - 1) as u16)
+                            }
+                        );
+
+                        // Load prescaler and auto-reload value and reset the
+                        // counter. Setting URS makes sure this doesn't set UIF.
+                        self.tim.cr1.modify(|_, w| w.urs().set_bit());
+                        self.tim.egr.write(|w| w.ug().set_bit());
+                        self.tim.sr.modify(|_, w| w.uif().clear_bit());
+
+                        self.tim.cr1.modify(|_, w| w.cen().set_bit());
+                        while self.tim.sr.read().uif().bit_is_clear() {}
+                        self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+                    }
+
+                    self.tim.sr.modify(|_, w| w.uif().clear_bit());
+                }
+
+                /// Enables or disables the auto-reload preload (`CR1.ARPE`)
+                ///
+                /// With preload enabled, a new auto-reload value only takes
+                /// effect at the next update event, so the period of a
+                /// running timer can be changed without a glitch.
+                pub fn set_arr_preload(&mut self, enabled: bool) {
+                    self.tim.cr1.modify(|_, w| w.arpe().bit(enabled));
+                }
+
                 /// Select master mode
                 pub fn select_master_mode(&mut self,
                     variant: <$TIM as GeneralPurposeTimer>::MasterMode,
@@ -175,16 +408,15 @@ macro_rules! timers {
                     // reset counter
                     self.tim.cnt.reset();
 
-                    let freq = timeout.into().0;
-                    let ticks = self.clocks.$timclk().0 / freq;
-                    let psc = u16((ticks - 1) / (1 << 16)).unwrap();
+                    let (psc, arr) = compute_psc_arr(self.clocks.$timclk(), timeout.into())
+                        .expect("Timeout out of range for this timer");
                     self.tim.psc.write(|w| w.psc().bits(psc));
                     // This is only unsafe for some timers, so we need this to
                     // suppress the warnings.
                     #[allow(unused_unsafe)]
                     self.tim.arr.write(|w|
                         unsafe {
-                            w.arr().bits(u16(ticks / u32(psc + 1)).unwrap())
+                            w.arr().bits(arr)
                         }
                     );
 
@@ -213,8 +445,7 @@ macro_rules! timers {
 
                 fn enable(&mut self, rcc: &mut Rcc) {
                     rcc.rb.$apbenr.modify(|_, w| w.$timXen().set_bit());
-                    rcc.rb.$apbrstr.modify(|_, w| w.$timXrst().set_bit());
-                    rcc.rb.$apbrstr.modify(|_, w| w.$timXrst().clear_bit());
+                    rcc.reset::<$TIM>();
                 }
 
                 fn select_master_mode(&mut self, variant: Self::MasterMode) {
@@ -225,6 +456,142 @@ macro_rules! timers {
     }
 }
 
+macro_rules! external_clock {
+    ($($TIM:ident,)+) => {
+        $(
+            impl Timer<$TIM> {
+                /// Clocks the counter from the ETR pin (external clock mode 2)
+                ///
+                /// The counter is reset and counts up to the auto-reload value
+                /// on every active edge of the prescaled and filtered ETR
+                /// signal, so [`counter`] returns the number of edges. Use
+                /// [`set_reload`] to generate an update event every `n` edges
+                /// instead, dividing the external clock.
+                ///
+                /// The ETR pin must be put into the right alternate function by
+                /// the caller.
+                ///
+                /// # Panics
+                ///
+                /// Panics, if the filter value is larger than 15.
+                ///
+                /// [`counter`]: #method.counter
+                /// [`set_reload`]: #method.set_reload
+                pub fn clock_from_etr(&mut self, config: EtrConfig) {
+                    assert!(config.filter < 16);
+
+                    self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+
+                    // This is only unsafe for some timers, so we need this to
+                    // suppress the warnings.
+                    #[allow(unused_unsafe)]
+                    self.tim.smcr.write(|w| unsafe {
+                        w.etp()
+                            .bit(config.polarity == ClockPolarity::Falling)
+                            .etps()
+                            .bits(config.prescaler as u8)
+                            .etf()
+                            .bits(config.filter)
+                            .ece()
+                            .set_bit()
+                    });
+
+                    self.start_external();
+                }
+
+                /// Clocks the counter from a timer input (external clock mode 1)
+                ///
+                /// The counter is reset and counts up to the auto-reload value
+                /// on every active edge of the filtered input, see
+                /// [`clock_from_etr`] for how to read or divide it. The filter
+                /// value has the same meaning as for [`EtrConfig`].
+                ///
+                /// The input pin must be put into the right alternate function
+                /// by the caller.
+                ///
+                /// # Panics
+                ///
+                /// Panics, if the filter value is larger than 15.
+                ///
+                /// [`clock_from_etr`]: #method.clock_from_etr
+                /// [`EtrConfig`]: struct.EtrConfig.html
+                pub fn clock_from_ti(&mut self, input: ClockInput, polarity: ClockPolarity, filter: u8) {
+                    assert!(filter < 16);
+
+                    let falling = polarity == ClockPolarity::Falling;
+
+                    self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+
+                    // Map the input to its capture channel, so the filter and
+                    // polarity settings apply
+                    #[allow(unused_unsafe)]
+                    match input {
+                        ClockInput::Ti1 | ClockInput::Ti1BothEdges => {
+                            self.tim.ccer.modify(|_, w| w.cc1e().clear_bit());
+                            self.tim.ccmr1_input().modify(|_, w| unsafe {
+                                w.cc1s().bits(0b01).ic1f().bits(filter)
+                            });
+                            self.tim.ccer.modify(|_, w| w.cc1p().bit(falling).cc1np().clear_bit());
+                        }
+                        ClockInput::Ti2 => {
+                            self.tim.ccer.modify(|_, w| w.cc2e().clear_bit());
+                            self.tim.ccmr1_input().modify(|_, w| unsafe {
+                                w.cc2s().bits(0b01).ic2f().bits(filter)
+                            });
+                            self.tim.ccer.modify(|_, w| w.cc2p().bit(falling).cc2np().clear_bit());
+                        }
+                    }
+
+                    self.tim.smcr.write(|w| {
+                        let w = match input {
+                            ClockInput::Ti1 => w.ts().ti1fp1(),
+                            ClockInput::Ti2 => w.ts().ti2fp2(),
+                            ClockInput::Ti1BothEdges => w.ts().ti1f_ed(),
+                        };
+                        w.sms().ext_clock_mode()
+                    });
+
+                    self.start_external();
+                }
+
+                /// Clocks the counter from the internal timer clock again
+                ///
+                /// This disables both external clock modes and stops the
+                /// counter. Use `start` to restart it with a timeout.
+                pub fn clock_internal(&mut self) {
+                    self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+                    self.tim.smcr.reset();
+                }
+
+                /// Sets the auto-reload value
+                ///
+                /// When counting an external clock, an update event is
+                /// generated every `arr + 1` edges, so `wait` and the update
+                /// interrupt can be used to divide the clock.
+                pub fn set_reload(&mut self, arr: u16) {
+                    // This is only unsafe for some timers, so we need this to
+                    // suppress the warnings.
+                    #[allow(unused_unsafe)]
+                    self.tim.arr.write(|w| unsafe { w.arr().bits(arr) });
+                }
+
+                fn start_external(&mut self) {
+                    self.tim.psc.write(|w| w.psc().bits(0));
+                    self.set_reload(0xffff);
+
+                    // Load prescaler and auto-reload value and reset the
+                    // counter. Setting URS makes sure this doesn't set UIF.
+                    self.tim.cr1.modify(|_, w| w.urs().set_bit());
+                    self.tim.egr.write(|w| w.ug().set_bit());
+                    self.tim.sr.modify(|_, w| w.uif().clear_bit());
+
+                    self.tim.cr1.modify(|_, w| w.cen().set_bit());
+                }
+            }
+        )+
+    }
+}
+
 /// Two linked 16 bit timers that form a 32 bit timer.
 pub trait LinkedTimer {
     /// Return the current 16 bit counter value of the MSB timer.
@@ -256,9 +623,8 @@ macro_rules! linked_timers {
     ($(
         ($PRIMARY:ident, $SECONDARY:ident): (
             $new:ident,
-            $apbenr:ident, $apbrstr:ident,
+            $apbenr:ident,
             $master_en:ident, $slave_en:ident,
-            $master_rst:ident, $slave_rst:ident,
             $mms:ty, $sms:ty, $ts:expr
         ),
     )+) => {
@@ -272,10 +638,8 @@ macro_rules! linked_timers {
                     rcc.rb.$apbenr.modify(|_, w| w.$slave_en().set_bit());
 
                     // Reset timers
-                    rcc.rb.$apbrstr.modify(|_, w| w.$master_rst().set_bit());
-                    rcc.rb.$apbrstr.modify(|_, w| w.$master_rst().clear_bit());
-                    rcc.rb.$apbrstr.modify(|_, w| w.$slave_rst().set_bit());
-                    rcc.rb.$apbrstr.modify(|_, w| w.$slave_rst().clear_bit());
+                    rcc.reset::<$PRIMARY>();
+                    rcc.reset::<$SECONDARY>();
 
                     // Enable counter
                     tim_primary.cr1.modify(|_, w| w.cen().set_bit());
@@ -347,24 +711,246 @@ macro_rules! linked_timers {
 }
 
 timers! {
-    TIM2: (tim2, tim2en, tim2rst, apb1enr, apb1rstr, apb1_tim_clk,
+    TIM2: (tim2, tim2en, apb1enr, apb1_tim_clk,
         tim2::cr2::MMS_A),
-    TIM3: (tim3, tim3en, tim3rst, apb1enr, apb1rstr, apb1_tim_clk,
+    TIM3: (tim3, tim3en, apb1enr, apb1_tim_clk,
         tim2::cr2::MMS_A),
-    TIM6: (tim6, tim6en, tim6rst, apb1enr, apb1rstr, apb1_tim_clk,
+    TIM6: (tim6, tim6en, apb1enr, apb1_tim_clk,
         tim6::cr2::MMS_A),
-    TIM21: (tim21, tim21en, tim21rst, apb2enr, apb2rstr, apb2_tim_clk,
+    TIM21: (tim21, tim21en, apb2enr, apb2_tim_clk,
         tim21::cr2::MMS_A),
-    TIM22: (tim22, tim22en, tim22rst, apb2enr, apb2rstr, apb2_tim_clk,
+    TIM22: (tim22, tim22en, apb2enr, apb2_tim_clk,
         tim22::cr2::MMS_A),
 }
 
+// TIM6 has no inputs and can only be clocked internally
+external_clock! {
+    TIM2,
+    TIM3,
+    TIM21,
+    TIM22,
+}
+
 linked_timers! {
     // Internal trigger connection: RM0377 table 76
-    (TIM2, TIM3): (tim2_tim3, apb1enr, apb1rstr, tim2en, tim3en, tim2rst, tim3rst, tim2::cr2::MMS_A, tim2::smcr::SMS_A, tim2::smcr::TS_A::ITR0),
+    (TIM2, TIM3): (tim2_tim3, apb1enr, tim2en, tim3en, tim2::cr2::MMS_A, tim2::smcr::SMS_A, tim2::smcr::TS_A::ITR0),
     // Internal trigger connection: RM0377 table 80
-    (TIM21, TIM22): (tim21_tim22, apb2enr, apb2rstr, tim21en, tim22en, tim21rst, tim22rst, tim21::cr2::MMS_A, tim22::smcr::SMS_A, tim22::smcr::TS_A::ITR0),
+    (TIM21, TIM22): (tim21_tim22, apb2enr, tim21en, tim22en, tim21::cr2::MMS_A, tim22::smcr::SMS_A, tim22::smcr::TS_A::ITR0),
 
     // Note: Other combinations would be possible as well, e.g. (TIM21, TIM2) or (TIM2, TIM22).
     // They can be implemented if needed.
 }
+
+/// A point in time, as measured by a [`CycleCounter`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Instant {
+    cycles: u32,
+}
+
+impl Instant {
+    /// Returns the raw counter value, in timer clock cycles
+    pub fn cycles(&self) -> u32 {
+        self.cycles
+    }
+}
+
+/// Measures time in clock cycles, for benchmarking
+///
+/// The Cortex-M0+ core doesn't have the DWT cycle counter, so this uses a
+/// [`LinkedTimerPair`] as a free-running 32 bit counter instead, which counts
+/// the cycles of the timer clock without a prescaler.
+///
+/// # Resolution
+///
+/// The resolution is one timer clock cycle. The timer clock equals the APB
+/// clock, or twice the APB clock if the APB prescaler is not 1. To count
+/// core clock cycles, the APB prescaler of the timers must be 1. The counter
+/// wraps around after 2^32 cycles, which is about 134 seconds at 32 MHz.
+/// Measurements that take longer than that are wrong.
+///
+/// # Overhead
+///
+/// Reading the counter takes a few register accesses, so every measurement
+/// includes a small constant overhead. It is measured when the counter is
+/// created and can be subtracted using [`CycleCounter::overhead_cycles`].
+pub struct CycleCounter<T> {
+    timers: T,
+    frequency: Hertz,
+    overhead: u32,
+}
+
+impl<T> CycleCounter<T>
+where
+    T: LinkedTimer,
+{
+    /// Creates a new `CycleCounter` and resets the counter
+    ///
+    /// `frequency` must be the clock frequency of the timers, for example
+    /// `rcc.clocks.apb2_tim_clk()` for the linked pair of TIM21 and TIM22.
+    pub fn new(mut timers: T, frequency: Hertz) -> Self {
+        timers.reset();
+
+        let mut counter = CycleCounter {
+            timers,
+            frequency,
+            overhead: 0,
+        };
+
+        let start = counter.now();
+        counter.overhead = counter.elapsed_cycles(start);
+
+        counter
+    }
+
+    /// Returns the current point in time
+    pub fn now(&self) -> Instant {
+        Instant {
+            cycles: self.timers.get_counter(),
+        }
+    }
+
+    /// Returns the number of cycles since `since`
+    pub fn elapsed_cycles(&self, since: Instant) -> u32 {
+        self.now().cycles.wrapping_sub(since.cycles)
+    }
+
+    /// Returns the number of microseconds since `since`, rounded down
+    pub fn elapsed_us(&self, since: Instant) -> u32 {
+        self.cycles_to_us(self.elapsed_cycles(since))
+    }
+
+    /// Converts a number of timer clock cycles to microseconds, rounding down
+    pub fn cycles_to_us(&self, cycles: u32) -> u32 {
+        (u64::from(cycles) * 1_000_000 / u64::from(self.frequency.0)) as u32
+    }
+
+    /// Returns the number of cycles measured between two calls to [`now`]
+    ///
+    /// [`now`]: #method.now
+    pub fn overhead_cycles(&self) -> u32 {
+        self.overhead
+    }
+
+    /// Releases the timers
+    pub fn free(self) -> T {
+        self.timers
+    }
+}
+
+/// Returns the number of ticks for the next auto-reload period of a delay
+///
+/// A period is at most 2^16 ticks long. It's never shorter than 2 ticks, as
+/// the timer doesn't count with `ARR = 0`, and never sets UIF. Remaining
+/// delays of a single tick are rounded up, and the periods before the last
+/// one are shortened, so the last one isn't a single tick.
+fn delay_chunk(remaining: u64) -> u64 {
+    const MAX_PERIOD: u64 = 1 << 16;
+
+    if remaining <= MAX_PERIOD {
+        remaining.max(2)
+    } else {
+        core::cmp::min(remaining - 2, MAX_PERIOD)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::U32Ext;
+
+    /// Returns the output frequency for the given register values
+    fn output_freq(clk: Hertz, (psc, arr): (u16, u16)) -> u32 {
+        clk.0 / ((u32::from(psc) + 1) * (u32::from(arr) + 1))
+    }
+
+    #[test]
+    fn exact_without_prescaler() {
+        let conf = compute_psc_arr(16.mhz(), 1.khz()).unwrap();
+        assert_eq!(conf, (0, 15_999));
+        assert_eq!(output_freq(16.mhz(), conf), 1_000);
+    }
+
+    #[test]
+    fn exact_with_prescaler() {
+        // 16_000_000 cycles per period don't fit into 16 bits
+        assert_eq!(compute_psc_arr(16.mhz(), 1.hz()), Some((244, 65_305)));
+        assert_eq!(compute_psc_arr(16.mhz(), 100.hz()), Some((2, 53_332)));
+    }
+
+    #[test]
+    fn largest_period_without_prescaler() {
+        assert_eq!(compute_psc_arr(65_536.hz(), 1.hz()), Some((0, 65_535)));
+        assert_eq!(compute_psc_arr(65_537.hz(), 1.hz()), Some((1, 32_768)));
+    }
+
+    #[test]
+    fn rounds_to_nearest() {
+        // 16 MHz / 3 Hz = 5_333_333.3 cycles
+        let (psc, arr) = compute_psc_arr(16.mhz(), 3.hz()).unwrap();
+        let cycles = (u32::from(psc) + 1) * (u32::from(arr) + 1);
+        assert!((cycles as i32 - 5_333_333).abs() <= i32::from(psc) + 1);
+    }
+
+    #[test]
+    fn smallest_period() {
+        assert_eq!(compute_psc_arr(16.mhz(), 8.mhz()), Some((0, 1)));
+        assert_eq!(output_freq(16.mhz(), (0, 1)), 8_000_000);
+    }
+
+    #[test]
+    fn largest_period() {
+        assert_eq!(
+            compute_psc_arr(u32::MAX.hz(), 1.hz()),
+            Some((65_535, 65_535))
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(compute_psc_arr(16.mhz(), 0.hz()), None);
+        assert_eq!(compute_psc_arr(16.mhz(), 9.mhz()), None);
+        assert_eq!(compute_psc_arr(16.mhz(), 11.mhz()), None);
+        assert_eq!(compute_psc_arr(16.mhz(), 16.mhz()), None);
+        assert_eq!(compute_psc_arr(16.mhz(), 32.mhz()), None);
+    }
+
+    #[test]
+    fn status_from_bits() {
+        assert_eq!(TimerStatus::from_bits(0), TimerStatus::default());
+
+        let status = TimerStatus::from_bits(0b1_0100_0100_0011);
+        assert!(status.update);
+        assert_eq!(status.capture_compare, [true, false, false, false]);
+        assert!(status.trigger);
+        assert_eq!(status.overcapture, [false, true, false, true]);
+    }
+
+    /// Returns the periods a delay of `ticks` is split into
+    fn delay_chunks(mut ticks: u64) -> Vec<u64> {
+        let mut chunks = Vec::new();
+        while ticks > 0 {
+            let chunk = delay_chunk(ticks);
+            ticks = ticks.saturating_sub(chunk);
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    #[test]
+    fn delay_never_uses_single_tick_periods() {
+        assert_eq!(delay_chunks(0), Vec::<u64>::new());
+        assert_eq!(delay_chunks(1), vec![2]);
+        assert_eq!(delay_chunks(2), vec![2]);
+        assert_eq!(delay_chunks(65_536), vec![65_536]);
+        assert_eq!(delay_chunks(65_537), vec![65_535, 2]);
+        assert_eq!(delay_chunks(65_538), vec![65_536, 2]);
+        assert_eq!(delay_chunks(131_073), vec![65_536, 65_535, 2]);
+
+        for ticks in (1..200_000).step_by(7) {
+            let chunks = delay_chunks(ticks);
+            assert!(chunks.iter().all(|&c| (2..=65_536).contains(&c)));
+            assert!(chunks.iter().sum::<u64>() >= ticks);
+            assert!(chunks.iter().sum::<u64>() <= ticks.max(2));
+        }
+    }
+}